@@ -0,0 +1,208 @@
+use crate::game::Game;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ChestProps {
+    pub id: usize,
+    /// item_ids in global_items still lying in the chest
+    #[serde(default)]
+    pub items: Vec<usize>,
+    #[serde(default)]
+    pub gold: usize,
+    /// item_id in global_items needed to open the chest
+    pub key: Option<usize>,
+    /// damage dealt to the player the first time the chest is opened
+    pub trap_damage: Option<i32>,
+}
+
+/// state of the currently open loot dialog
+#[derive(Debug, Clone)]
+pub struct Loot {
+    /// index in Game::chests
+    pub chest: usize,
+    pub selected: usize,
+}
+
+impl Game {
+    pub fn open_chest(&mut self, chest_id: usize) {
+        let chest = match self.chests.iter().position(|c| c.id == chest_id) {
+            Some(v) => v,
+            None => {
+                log::warn!("chest {} not found", chest_id);
+                return;
+            }
+        };
+
+        if let Some(key) = self.chests[chest].key {
+            if self.inventory.position_of(key).is_none() {
+                self.info_queue
+                    .queue("Chest".to_string(), "The chest is locked".to_string());
+                return;
+            }
+        }
+
+        if let Some(damage) = self.chests[chest].trap_damage.take() {
+            self.info_queue.queue(
+                "Chest".to_string(),
+                format!("A trap in the chest hits you for {} damage", damage),
+            );
+            self.damage_player(damage);
+            if self.is_defeated() {
+                return;
+            }
+        }
+
+        let gold = std::mem::take(&mut self.chests[chest].gold);
+        if gold > 0 {
+            self.gold += gold;
+            self.info_queue
+                .queue("Chest".to_string(), format!("You have found {} gold", gold));
+        }
+
+        if self.chests[chest].items.is_empty() {
+            self.info_queue
+                .queue("Chest".to_string(), "The chest is empty".to_string());
+            return;
+        }
+
+        self.loot = Some(Loot { chest, selected: 0 });
+        self.active_menu_item = crate::MenuItem::Loot;
+    }
+
+    pub fn close_loot(&mut self) {
+        self.loot = None;
+        self.active_menu_item = crate::MenuItem::Game;
+    }
+
+    pub fn loot_up(&mut self) {
+        if let Some(loot) = self.loot.as_mut() {
+            loot.selected = loot.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn loot_down(&mut self) {
+        if let Some(loot) = self.loot.as_mut() {
+            if loot.selected + 1 < self.chests[loot.chest].items.len() {
+                loot.selected += 1;
+            }
+        }
+    }
+
+    pub fn take_selected_loot(&mut self) {
+        let loot = match self.loot.clone() {
+            Some(v) => v,
+            None => return,
+        };
+        self.take_loot(loot.chest, loot.selected);
+
+        let len = self.chests[loot.chest].items.len();
+        if len == 0 {
+            self.close_loot();
+        } else if let Some(loot) = self.loot.as_mut() {
+            loot.selected = loot.selected.min(len - 1);
+        }
+    }
+
+    pub fn take_all_loot(&mut self) {
+        let loot = match self.loot.clone() {
+            Some(v) => v,
+            None => return,
+        };
+        let mut index = 0;
+        while index < self.chests[loot.chest].items.len() {
+            if !self.take_loot(loot.chest, index) {
+                index += 1;
+            }
+        }
+        self.close_loot();
+    }
+
+    /// moves an item from the chest into the inventory, it stays in the chest if that fails
+    fn take_loot(&mut self, chest: usize, index: usize) -> bool {
+        let item_id = self.chests[chest].items[index];
+        match self.pick_up_item(item_id) {
+            Ok(()) => {
+                self.chests[chest].items.remove(index);
+                let name = self.inventory.inventory.last().unwrap().props.name.clone();
+                self.info_queue.queue(
+                    "Item".to_string(),
+                    "You have collected a ".to_string() + &name,
+                );
+                true
+            }
+            Err(e) => {
+                log::warn!("{} (item {} in chest {})", e, item_id, chest);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+
+    fn chest_game(chest: &str) -> Game {
+        Game::from_yaml(&format!("chests:\n    - {}\n", chest))
+    }
+
+    #[test]
+    fn test_take_all_loot() {
+        let mut game = chest_game("{id: 0, items: [0, 2], gold: 7}");
+
+        game.open_chest(0);
+        game.take_all_loot();
+
+        assert_eq!(game.gold, 7);
+        assert_eq!(game.inventory.inventory.len(), 2);
+        assert!(game.chests[0].items.is_empty());
+        assert!(game.loot.is_none());
+    }
+
+    #[test]
+    fn test_take_selected_loot() {
+        let mut game = chest_game("{id: 0, items: [0, 2]}");
+
+        game.open_chest(0);
+        game.loot_down();
+        game.take_selected_loot();
+
+        assert_eq!(game.inventory.inventory[0].props.id, 2);
+        assert_eq!(game.chests[0].items, vec![0]);
+    }
+
+    #[test]
+    fn test_locked_trapped_chest() {
+        let mut game = chest_game("{id: 0, items: [0], key: 3, trap_damage: 4}");
+
+        game.open_chest(0);
+        assert!(game.loot.is_none());
+
+        game.inventory.add_item(3, &game.global_items).unwrap();
+        game.open_chest(0);
+        game.open_chest(0);
+        assert!(game.loot.is_some());
+        assert_eq!(game.entities[0].hp, 26);
+    }
+
+    #[test]
+    fn test_keep_unknown_loot() {
+        let mut game = chest_game("{id: 0, items: [9, 0]}");
+
+        game.open_chest(0);
+        game.take_all_loot();
+
+        assert_eq!(game.inventory.inventory.len(), 1);
+        assert_eq!(game.chests[0].items, vec![9]);
+    }
+
+    #[test]
+    fn test_lethal_chest_trap() {
+        let mut game = chest_game("{id: 0, items: [0], trap_damage: 40}");
+
+        game.open_chest(0);
+
+        assert!(game.is_defeated());
+        assert!(game.loot.is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::game::{Game, DEFAULT_SCENARIO};
+use log::LevelFilter;
+
+pub const USAGE: &str = "Easy Dungeon Scrawl - a simple rpg dungeon scrawl game
+
+Usage: easy-dungeon-scrawl [COMMAND] [OPTIONS] [SCENARIO]
+
+Commands:
+    play          play the scenario (default)
+    validate      check the scenario file for mistakes
+    render-map    print the maps of the scenario
+    edit          paint the maps of the scenario in the terminal
+
+Options:
+    -s, --scenario <FILE>    scenario file to use [default: test.yaml]
+        --seed <NUMBER>      seed for the dice rolls
+        --slot <NUMBER>      save slot to load when starting to play
+        --map <NUMBER>       only render this map
+        --log-file <FILE>    file the log is written to [default: output.log]
+        --log-level <LEVEL>  off, error, warn, info, debug or trace [default: info]
+    -h, --help               print this help";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Play,
+    Validate,
+    /// index of the map to render, all maps if not set
+    RenderMap(Option<usize>),
+    Edit,
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub command: Command,
+    pub scenario: String,
+    pub seed: Option<u64>,
+    pub slot: Option<usize>,
+    pub log_file: String,
+    pub log_level: LevelFilter,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            command: Command::Play,
+            scenario: DEFAULT_SCENARIO.to_string(),
+            seed: None,
+            slot: None,
+            log_file: "output.log".to_string(),
+            log_level: LevelFilter::Info,
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got '{}'", option, value))
+}
+
+/// parses the arguments without the program name
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut map = None;
+    let mut command = None;
+    let mut scenario = None;
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // options can be given as `--name value` or `--name=value`
+        let (name, inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| match inline_value {
+            Some(value) => Ok(value.to_string()),
+            None => args.next().ok_or(format!("{} expects a value", name)),
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => command = Some(Command::Help),
+            "-s" | "--scenario" => scenario = Some(value(&name)?),
+            "--seed" => options.seed = Some(parse_number(&name, &value(&name)?)?),
+            "--slot" => {
+                let slot = parse_number(&name, &value(&name)?)?;
+                if !(1..=crate::save::SAVE_SLOTS).contains(&slot) {
+                    return Err(format!(
+                        "--slot must be between 1 and {}",
+                        crate::save::SAVE_SLOTS
+                    ));
+                }
+                options.slot = Some(slot);
+            }
+            "--map" => map = Some(parse_number(&name, &value(&name)?)?),
+            "--log-file" => options.log_file = value(&name)?,
+            "--log-level" => {
+                let level = value(&name)?;
+                options.log_level = level
+                    .parse()
+                    .map_err(|_| format!("unknown log level '{}'", level))?;
+            }
+            _ if name.starts_with('-') => return Err(format!("unknown option '{}'", name)),
+            "play" if command.is_none() && scenario.is_none() => command = Some(Command::Play),
+            "validate" if command.is_none() && scenario.is_none() => {
+                command = Some(Command::Validate)
+            }
+            "render-map" if command.is_none() && scenario.is_none() => {
+                command = Some(Command::RenderMap(None))
+            }
+            "edit" if command.is_none() && scenario.is_none() => command = Some(Command::Edit),
+            _ if scenario.is_none() => scenario = Some(arg),
+            _ => return Err(format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    options.command = match command.unwrap_or(Command::Play) {
+        Command::RenderMap(_) => Command::RenderMap(map),
+        _ if map.is_some() => return Err("--map only works with render-map".to_string()),
+        command => command,
+    };
+    if let Some(scenario) = scenario {
+        options.scenario = scenario;
+    }
+    Ok(options)
+}
+
+/// prints every problem of the scenario, returns false if there are errors
+pub fn validate(options: &Options) -> bool {
+    let diagnostics = crate::validate::validate_file(&options.scenario);
+    for diagnostic in &diagnostics {
+        eprintln!("{}:{}", options.scenario, diagnostic);
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == crate::validate::Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+    if diagnostics.is_empty() {
+        println!("{}: ok", options.scenario);
+    } else {
+        eprintln!("{} errors, {} warnings", errors, warnings);
+    }
+    errors == 0
+}
+
+/// prints the maps of the scenario the way they are shown in the game
+pub fn render_map(options: &Options, map: Option<usize>) -> bool {
+    let game = match Game::with_scenario(&options.scenario) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("{}", e);
+            return false;
+        }
+    };
+    let maps: Vec<usize> = match map {
+        Some(map) if map >= game.maps.len() => {
+            eprintln!("the scenario has no map {}", map);
+            return false;
+        }
+        Some(map) => vec![map],
+        None => (0..game.maps.len()).collect(),
+    };
+
+    for map in maps {
+        let pos = if map == game.cur_map {
+            Some(&game.pos)
+        } else {
+            None
+        };
+        println!("map {}", map);
+        println!("{}", crate::map_gen::visulize_map(&game.maps[map], pos));
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{parse_args, Command, Options};
+    use log::LevelFilter;
+
+    fn parse(args: &str) -> Result<Options, String> {
+        parse_args(args.split_whitespace().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn test_defaults() {
+        assert_eq!(parse("").unwrap(), Options::default());
+        assert_eq!(parse("play").unwrap(), Options::default());
+    }
+
+    #[test]
+    fn test_options() {
+        let options =
+            parse("play dungeon.yaml --seed 42 --slot=2 --log-file game.log --log-level debug")
+                .unwrap();
+
+        assert_eq!(options.command, Command::Play);
+        assert_eq!(options.scenario, "dungeon.yaml");
+        assert_eq!(options.seed, Some(42));
+        assert_eq!(options.slot, Some(2));
+        assert_eq!(options.log_file, "game.log");
+        assert_eq!(options.log_level, LevelFilter::Debug);
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(
+            parse("validate -s a.yaml").unwrap().command,
+            Command::Validate
+        );
+        assert_eq!(
+            parse("render-map --map 1").unwrap().command,
+            Command::RenderMap(Some(1))
+        );
+        assert_eq!(parse("edit a.yaml").unwrap().command, Command::Edit);
+        assert_eq!(parse("play --help").unwrap().command, Command::Help);
+        // a scenario file may be called like a command
+        assert_eq!(parse("validate play").unwrap().scenario, "play");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("--seed").is_err());
+        assert!(parse("--seed abc").is_err());
+        assert!(parse("--slot 9").is_err());
+        assert!(parse("--map 1").is_err());
+        assert!(parse("--fast").is_err());
+        assert!(parse("a.yaml b.yaml").is_err());
+    }
+}
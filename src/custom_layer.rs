@@ -1,20 +1,102 @@
-use serde::Deserialize;
+use crate::map_gen::MapError;
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+/// why a scenario could not be loaded
+#[derive(Debug, Error)]
+pub enum ScenarioError {
+    #[error("can not read {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("{path} is not a valid scenario: {source}")]
+    Parse {
+        path: String,
+        source: serde_yaml::Error,
+    },
+    #[error("{path}: {source}")]
+    Map { path: String, source: MapError },
+    #[error("{path}: {source}")]
+    Edge {
+        path: String,
+        source: crate::edges::EdgeError,
+    },
+    #[error("{path}: map {map}: {source}")]
+    Tiled {
+        path: String,
+        map: usize,
+        source: crate::tiled::TiledError,
+    },
+    #[error("{path}: conditional tile {id}: {source}")]
+    Conditional {
+        path: String,
+        id: usize,
+        source: crate::map_gen::TileError,
+    },
+    #[error("{path}: start_pos [{}, {}] is outside of map 0", .start_pos[0], .start_pos[1])]
+    StartOutside { path: String, start_pos: [usize; 2] },
+    #[error("{path}: start_pos [{}, {}] is inside a wall", .start_pos[0], .start_pos[1])]
+    StartInWall { path: String, start_pos: [usize; 2] },
+    #[error("{path}: {source}")]
+    Depth {
+        path: String,
+        source: crate::depth::DepthError,
+    },
+}
 
 #[derive(Deserialize, Debug)]
 pub struct GameSettings {
-    pub maps: Vec<String>,
+    pub maps: Vec<crate::map_gen::MapDefinition>,
+    /// characters used by the grid maps
+    #[serde(default)]
+    pub legend: crate::map_gen::Legend,
     pub global_items: Vec<crate::ItemProps>,
     pub player: GameSettingsPlayer,
     pub start_pos: [usize; 2],
     pub enemies: Vec<crate::Entity>,
+    #[serde(default)]
+    pub merchants: Vec<crate::merchant::Merchant>,
+    #[serde(default)]
+    pub doors: Vec<crate::door::DoorProps>,
+    #[serde(default)]
+    pub levers: Vec<crate::door::LeverProps>,
+    #[serde(default)]
+    pub chests: Vec<crate::chest::ChestProps>,
+    #[serde(default)]
+    pub traps: Vec<crate::trap::TrapProps>,
+    #[serde(default)]
+    pub shrines: Vec<crate::shrine::ShrineProps>,
+    #[serde(default)]
+    pub npcs: Vec<crate::dialogue::Npc>,
+    #[serde(default)]
+    pub quests: Vec<crate::quest::Quest>,
+    #[serde(default)]
+    pub conditional_tiles: Vec<crate::flags::ConditionalTileDefinition>,
+    #[serde(default)]
+    pub events: Vec<crate::events::Event>,
+    #[serde(default)]
+    pub scripts: Vec<crate::script::Script>,
+    #[serde(default)]
+    pub readables: Vec<crate::lore::Readable>,
+    /// levels below the maps, reached by stairs
+    #[serde(default)]
+    pub dungeon: Option<crate::depth::Dungeon>,
+    /// hash of the scenario file, saves remember it to notice a changed scenario
+    #[serde(skip)]
+    pub content_hash: u64,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct RpProperties {
     pub at: i32,
     pub aw: i32,
     pub rs: i32,
+    /// bonus on the roll to spot hidden traps
+    #[serde(default)]
+    pub perception: i32,
 }
 
 #[derive(Deserialize, Debug)]
@@ -22,22 +104,96 @@ pub struct GameSettingsPlayer {
     pub name: String,
     pub character_type: String,
     pub total_health: i32,
+    #[serde(default)]
+    pub gold: usize,
     pub rp_properties: RpProperties,
 }
 
-pub fn parse_game_settings(file_name: &str) -> Result<GameSettings, serde_yaml::Error> {
-    let contents = fs::read_to_string(file_name).expect("to be able to open the file");
-    let game_settings: Result<GameSettings, serde_yaml::Error> = serde_yaml::from_str(&contents);
-    return game_settings;
+pub fn parse_game_settings(file_name: &str) -> Result<GameSettings, ScenarioError> {
+    let contents = fs::read_to_string(file_name).map_err(|source| ScenarioError::Read {
+        path: file_name.to_string(),
+        source,
+    })?;
+    parse_scenario(&contents, file_name)
+}
+
+/// the settings of a scenario read from `file_name`, maps made in tiled are read next to it
+pub fn parse_scenario(contents: &str, file_name: &str) -> Result<GameSettings, ScenarioError> {
+    let mut game_settings: GameSettings =
+        serde_yaml::from_str(contents).map_err(|source| ScenarioError::Parse {
+            path: file_name.to_string(),
+            source,
+        })?;
+    let dir = Path::new(file_name).parent().unwrap_or(Path::new(""));
+    crate::tiled::load_maps(&mut game_settings.maps, dir).map_err(|(map, source)| {
+        ScenarioError::Tiled {
+            path: file_name.to_string(),
+            map,
+            source,
+        }
+    })?;
+    game_settings.content_hash = content_hash(contents);
+    return Ok(game_settings);
+}
+
+/// fnv-1a, unlike the hasher of the standard library it stays the same between rust versions
+pub fn content_hash(contents: &str) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in contents.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
 }
 
 #[cfg(test)]
 mod tests {
-    use super::parse_game_settings;
+    use super::{content_hash, parse_game_settings, parse_scenario, ScenarioError};
+    use crate::game::Game;
+
+    fn load_error(start_pos: &str) -> ScenarioError {
+        let contents = format!(
+            "
+maps: ['|_|x|']
+global_items: []
+enemies: []
+start_pos: {}
+player: {{name: Hans, character_type: Warrior, total_health: 30, rp_properties: {{at: 1, aw: 6, rs: 2}}}}
+",
+            start_pos
+        );
+        let game_settings = parse_scenario(&contents, "start.yaml").unwrap();
+        Game::with_settings(game_settings, "start.yaml")
+            .err()
+            .unwrap()
+    }
 
     #[test]
     fn test_parse_game_settings() {
         let game_settings = parse_game_settings("test.yaml").unwrap();
         println!("{:?}", game_settings);
     }
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(content_hash(""), 0xcbf29ce484222325);
+        assert_eq!(content_hash("a"), 0xaf63dc4c8601ec8c);
+        assert_ne!(content_hash("maps: []"), content_hash("maps: [ ]"));
+    }
+
+    #[test]
+    fn test_start_pos_outside_of_map() {
+        assert_eq!(
+            load_error("[50, 50]").to_string(),
+            "start.yaml: start_pos [50, 50] is outside of map 0"
+        );
+    }
+
+    #[test]
+    fn test_start_pos_inside_a_wall() {
+        assert_eq!(
+            load_error("[1, 0]").to_string(),
+            "start.yaml: start_pos [1, 0] is inside a wall"
+        );
+    }
 }
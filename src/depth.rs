@@ -0,0 +1,315 @@
+use crate::edges::Edges;
+use crate::game::{Game, MapBlockTypes, Pos};
+use crate::map_gen::{Map, MapDefinition, MapRef, Names};
+use crate::procgen::Generator;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// the levels below the maps of the scenario, joined by stairs
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Dungeon {
+    /// maps of the first levels by index or name, the first one is at depth 1
+    #[serde(default)]
+    pub levels: Vec<MapRef>,
+    /// makes the levels below the listed ones the first time the player gets there
+    pub generate: Option<Generator>,
+    /// the deepest level, a generated one has no stairs down
+    pub max_depth: Option<usize>,
+}
+
+/// where the player went down the stairs, going up leads back there
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Descent {
+    pub map: usize,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum DepthError {
+    #[error("level {depth} of the dungeon is map '{target}', which does not exist")]
+    UnknownMap { depth: usize, target: String },
+    #[error("map {map} is level {depth} of the dungeon, but has no stairs up to arrive on")]
+    NoStairsUp { depth: usize, map: usize },
+}
+
+/// the stairs up of a map, players coming down arrive on the first one
+pub fn stairs_up(map: &Map) -> Vec<Pos> {
+    let mut stairs = vec![];
+    for (j, row) in map.iter().enumerate() {
+        for (i, block) in row.iter().enumerate() {
+            if *block == MapBlockTypes::StairsUp {
+                stairs.push(Pos { i, j });
+            }
+        }
+    }
+    stairs
+}
+
+/// maps of the listed levels by depth and every problem with them,
+/// levels which do not exist are left out
+pub fn collect_levels(
+    dungeon: Option<&Dungeon>,
+    definitions: &[MapDefinition],
+    maps: &[Map],
+) -> (Vec<usize>, Vec<DepthError>) {
+    let dungeon = match dungeon {
+        Some(dungeon) => dungeon,
+        None => return (vec![], vec![]),
+    };
+    let names = Names::of_maps(definitions);
+    let mut levels = vec![];
+    let mut errors = vec![];
+    for (index, level) in dungeon.levels.iter().enumerate() {
+        let depth = index + 1;
+        let map = match level {
+            MapRef::Index(map) => Some(*map).filter(|map| *map < maps.len()),
+            MapRef::Name(name) => names.map(name).ok().filter(|map| *map < maps.len()),
+        };
+        match map {
+            Some(map) if stairs_up(&maps[map]).is_empty() => {
+                errors.push(DepthError::NoStairsUp { depth, map })
+            }
+            Some(map) => levels.push(map),
+            None => errors.push(DepthError::UnknownMap {
+                depth,
+                target: match level {
+                    MapRef::Index(map) => map.to_string(),
+                    MapRef::Name(name) => name.clone(),
+                },
+            }),
+        }
+    }
+    (levels, errors)
+}
+
+/// like collect_levels, but stops at the first problem
+pub fn resolve_levels(
+    dungeon: Option<&Dungeon>,
+    definitions: &[MapDefinition],
+    maps: &[Map],
+) -> Result<Vec<usize>, DepthError> {
+    let (levels, mut errors) = collect_levels(dungeon, definitions, maps);
+    if errors.is_empty() {
+        Ok(levels)
+    } else {
+        Err(errors.remove(0))
+    }
+}
+
+impl Game {
+    /// how many levels below the maps of the scenario the player is
+    pub fn depth(&self) -> usize {
+        self.descents.len()
+    }
+
+    fn standing_on(&self) -> &MapBlockTypes {
+        self.resolve_block(&self.maps[self.cur_map][self.pos.j][self.pos.i])
+    }
+
+    pub fn descend(&mut self) {
+        if *self.standing_on() != MapBlockTypes::StairsDown {
+            self.info_queue.queue(
+                "Stairs".to_string(),
+                "There are no stairs down here".to_string(),
+            );
+            return;
+        }
+        let depth = self.depth() + 1;
+        let entry = self
+            .level(depth)
+            .and_then(|map| Some((map, stairs_up(&self.maps[map]).into_iter().next()?)));
+        match entry {
+            Some((map, pos)) => {
+                self.descents.push(Descent {
+                    map: self.cur_map,
+                    pos: self.pos.clone(),
+                });
+                self.enter_map(map, pos);
+                self.info_queue.queue(
+                    "Stairs".to_string(),
+                    format!("You go down to depth {}", depth),
+                );
+            }
+            None => self.info_queue.queue(
+                "Stairs".to_string(),
+                "The stairs are blocked by rubble".to_string(),
+            ),
+        }
+    }
+
+    pub fn ascend(&mut self) {
+        if *self.standing_on() != MapBlockTypes::StairsUp {
+            self.info_queue.queue(
+                "Stairs".to_string(),
+                "There are no stairs up here".to_string(),
+            );
+            return;
+        }
+        match self.descents.pop() {
+            Some(descent) => {
+                self.enter_map(descent.map, descent.pos);
+                let message = match self.depth() {
+                    0 => "You are back on the surface".to_string(),
+                    depth => format!("You go up to depth {}", depth),
+                };
+                self.info_queue.queue("Stairs".to_string(), message);
+            }
+            None => self
+                .info_queue
+                .queue("Stairs".to_string(), "The stairs lead nowhere".to_string()),
+        }
+    }
+
+    /// map of the level at `depth`, levels below the listed ones are generated when first needed
+    fn level(&mut self, depth: usize) -> Option<usize> {
+        if let Some(map) = self.levels.get(depth - 1) {
+            return Some(*map);
+        }
+        let dungeon = self.dungeon.as_ref()?;
+        let generator = dungeon.generate.as_ref()?;
+        if dungeon.max_depth.is_some_and(|max| depth > max) {
+            return None;
+        }
+        let map = self.maps.len();
+        let stairs_down = dungeon.max_depth != Some(depth);
+        let level = generator
+            .generate_level(map, depth, stairs_down)
+            .and_then(|raw| crate::map_gen::resolve_map(map, &raw, &Names::default()));
+        match level {
+            Ok(level) => {
+                log::info!("generated map {} as level {} of the dungeon", map, depth);
+                self.maps.push(level);
+                self.edges.push(Edges::new());
+                self.levels.push(map);
+                Some(map)
+            }
+            Err(e) => {
+                log::error!("can not generate level {} of the dungeon: {}", depth, e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::depth::{resolve_levels, stairs_up, DepthError, Dungeon};
+    use crate::game::{Game, MapBlockTypes, Pos};
+    use crate::map_gen::{generate_maps, Legend, MapDefinition};
+
+    fn definitions(yaml: &str) -> Vec<MapDefinition> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_levels() {
+        let definitions = definitions(
+            "- '|_|>|'
+- name: crypt
+  tiles: '|<|>|'
+- '|_|_|'",
+        );
+        let maps = generate_maps(&definitions, &Legend::new()).unwrap();
+        let dungeon = |yaml: &str| serde_yaml::from_str::<Dungeon>(yaml).unwrap();
+
+        assert_eq!(
+            resolve_levels(Some(&dungeon("levels: [crypt]")), &definitions, &maps),
+            Ok(vec![1])
+        );
+        assert_eq!(resolve_levels(None, &definitions, &maps), Ok(vec![]));
+        assert_eq!(
+            resolve_levels(Some(&dungeon("levels: [crypt, 2]")), &definitions, &maps),
+            Err(DepthError::NoStairsUp { depth: 2, map: 2 })
+        );
+        assert_eq!(
+            resolve_levels(Some(&dungeon("levels: [cellar]")), &definitions, &maps)
+                .unwrap_err()
+                .to_string(),
+            "level 1 of the dungeon is map 'cellar', which does not exist"
+        );
+    }
+
+    #[test]
+    fn test_descend_and_ascend() {
+        let mut game = Game::from_yaml(
+            "
+maps:
+    - '|>|_|'
+dungeon:
+    generate:
+        algorithm: rooms
+        width: 20
+        height: 12
+        enemies: [{id: 0}, {id: 1, min_depth: 2}]
+        enemy_count: 1
+        enemies_per_depth: 1
+    max_depth: 2
+",
+        );
+        let maps = 1;
+
+        game.descend();
+        assert_eq!(game.depth(), 1);
+        assert_eq!(game.cur_map, maps);
+        assert_eq!(game.edges.len(), maps + 1);
+        assert_eq!(
+            game.maps[game.cur_map][game.pos.j][game.pos.i],
+            MapBlockTypes::StairsUp
+        );
+        let enemies = |map: &crate::map_gen::Map| -> Vec<usize> {
+            map.iter()
+                .flatten()
+                .filter_map(|block| match block {
+                    MapBlockTypes::EnemyTrigger(id) => Some(*id),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(enemies(&game.maps[maps]), vec![0, 0]);
+
+        // the deepest level has no stairs down
+        let down = game.maps[maps]
+            .iter()
+            .enumerate()
+            .find_map(|(j, row)| {
+                row.iter()
+                    .position(|block| *block == MapBlockTypes::StairsDown)
+                    .map(|i| Pos { i, j })
+            })
+            .unwrap();
+        game.pos = down;
+        game.descend();
+        assert_eq!(game.depth(), 2);
+        assert_eq!(enemies(&game.maps[maps + 1]).len(), 3);
+        assert!(!game.maps[maps + 1]
+            .iter()
+            .flatten()
+            .any(|block| *block == MapBlockTypes::StairsDown));
+
+        game.ascend();
+        assert_eq!((game.cur_map, game.depth()), (maps, 1));
+        game.pos = stairs_up(&game.maps[maps])[0].clone();
+        game.ascend();
+        assert_eq!(game.depth(), 0);
+        assert_eq!((game.cur_map, game.pos.j, game.pos.i), (0, 0, 0));
+
+        // the levels are kept, going down again leads to the same one
+        game.descend();
+        assert_eq!(game.cur_map, maps);
+        assert_eq!(game.maps.len(), maps + 2);
+
+        // not standing on stairs
+        game.pos = game.maps[maps]
+            .iter()
+            .enumerate()
+            .find_map(|(j, row)| {
+                row.iter()
+                    .position(|block| *block == MapBlockTypes::Path)
+                    .map(|i| Pos { i, j })
+            })
+            .unwrap();
+        game.ascend();
+        assert_eq!(game.depth(), 1);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::events::{Action, Condition};
+use crate::game::Game;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Npc {
+    pub id: usize,
+    pub name: String,
+    /// id of the node the dialogue starts with
+    pub start: String,
+    pub nodes: Vec<DialogueNode>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueNode {
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub choices: Vec<DialogueChoice>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueChoice {
+    pub text: String,
+    /// node the dialogue continues with, the dialogue ends if not set
+    pub next: Option<String>,
+    /// the choice is only shown if the condition is met
+    #[serde(default)]
+    pub condition: Condition,
+    #[serde(default)]
+    pub outcomes: Vec<Action>,
+}
+
+/// state of the currently open dialogue
+#[derive(Debug, Clone)]
+pub struct Dialogue {
+    /// index in Game::npcs
+    pub npc: usize,
+    pub node: String,
+    pub selected: usize,
+}
+
+impl Game {
+    pub fn start_dialogue(&mut self, npc_id: usize) {
+        let npc = match self.npcs.iter().position(|npc| npc.id == npc_id) {
+            Some(v) => v,
+            None => {
+                log::warn!("npc {} not found", npc_id);
+                return;
+            }
+        };
+        self.quest_event(crate::quest::QuestEvent::NpcTalked(npc_id));
+        self.dialogue = Some(Dialogue {
+            npc,
+            node: self.npcs[npc].start.clone(),
+            selected: 0,
+        });
+        self.active_menu_item = crate::MenuItem::Dialogue;
+    }
+
+    pub fn close_dialogue(&mut self) {
+        self.dialogue = None;
+        self.active_menu_item = crate::MenuItem::Game;
+    }
+
+    pub fn dialogue_node(&self) -> Option<&DialogueNode> {
+        let dialogue = self.dialogue.as_ref()?;
+        self.npcs[dialogue.npc]
+            .nodes
+            .iter()
+            .find(|node| node.id == dialogue.node)
+    }
+
+    /// choices of the current node whose condition is met
+    pub fn dialogue_choices(&self) -> Vec<&DialogueChoice> {
+        match self.dialogue_node() {
+            Some(node) => node
+                .choices
+                .iter()
+                .filter(|choice| choice.condition.is_met(self))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn dialogue_up(&mut self) {
+        if let Some(dialogue) = self.dialogue.as_mut() {
+            dialogue.selected = dialogue.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn dialogue_down(&mut self) {
+        let len = self.dialogue_choices().len();
+        if let Some(dialogue) = self.dialogue.as_mut() {
+            if dialogue.selected + 1 < len {
+                dialogue.selected += 1;
+            }
+        }
+    }
+
+    pub fn dialogue_choose(&mut self) {
+        let selected = match &self.dialogue {
+            Some(dialogue) => dialogue.selected,
+            None => return,
+        };
+        let choice = match self.dialogue_choices().get(selected) {
+            Some(&choice) => choice.clone(),
+            None => {
+                // nodes without choices end the dialogue
+                self.close_dialogue();
+                return;
+            }
+        };
+
+        // outcomes may open another menu, the dialogue only goes on if none did
+        let mut dialogue = self.dialogue.take().unwrap();
+        self.close_dialogue();
+        for outcome in &choice.outcomes {
+            self.apply_action(outcome);
+        }
+
+        if let Some(next) = choice.next {
+            if self.active_menu_item == crate::MenuItem::Game && self.dialogue.is_none() {
+                dialogue.node = next;
+                dialogue.selected = 0;
+                self.dialogue = Some(dialogue);
+                self.active_menu_item = crate::MenuItem::Dialogue;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::Action;
+    use crate::game::Game;
+    use crate::MenuItem;
+
+    fn dialogue_game() -> Game {
+        Game::from_yaml(
+            "
+npcs:
+    - id: 0
+      name: Hermit
+      start: hello
+      nodes:
+          - id: hello
+            text: Hello
+            choices:
+                - text: Give me the key
+                  condition:
+                      not_flag: got_key
+                  next: key
+                  outcomes:
+                      - !give_item 3
+                      - !set_flag got_key
+                - text: Fight me
+                  next: key
+                  outcomes:
+                      - !start_fight 0
+                - text: Bye
+          - id: key
+            text: Take it
+",
+        )
+    }
+
+    #[test]
+    fn test_parse_outcomes() {
+        let game = dialogue_game();
+        assert_eq!(
+            game.npcs[0].nodes[0].choices[0].outcomes,
+            vec![Action::GiveItem(3), Action::SetFlag("got_key".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_dialogue_choice() {
+        let mut game = dialogue_game();
+
+        game.start_dialogue(0);
+        assert_eq!(game.dialogue_choices().len(), 3);
+        game.dialogue_choose();
+
+        assert_eq!(game.dialogue.as_ref().unwrap().node, "key");
+        assert_eq!(game.inventory.inventory[0].props.id, 3);
+        assert!(game.flags.is_set("got_key"));
+
+        game.dialogue_choose();
+        assert!(game.dialogue.is_none());
+
+        game.start_dialogue(0);
+        assert_eq!(game.dialogue_choices().len(), 2);
+    }
+
+    #[test]
+    fn test_dialogue_outcome_opens_fight() {
+        let mut game = dialogue_game();
+
+        game.start_dialogue(0);
+        game.dialogue_down();
+        game.dialogue_choose();
+
+        assert!(game.dialogue.is_none());
+        assert!(game.fight.is_some());
+        assert_eq!(game.active_menu_item, MenuItem::Fight);
+    }
+}
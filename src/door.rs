@@ -0,0 +1,148 @@
+use crate::game::{Game, MapBlockTypes, Pos};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DoorProps {
+    pub id: usize,
+    /// item_id in global_items which unlocks the door
+    pub key: Option<usize>,
+    /// remove the key from the inventory once the door is unlocked
+    #[serde(default)]
+    pub consume_key: bool,
+    /// flag which opens the door once it is set, e.g. by a lever
+    pub flag: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LeverProps {
+    pub id: usize,
+    /// flag set when the lever is pulled
+    pub flag: String,
+}
+
+impl Game {
+    fn get_door_props(&self, door_id: usize) -> Option<DoorProps> {
+        self.doors.iter().find(|door| door.id == door_id).cloned()
+    }
+
+    /// tries to open the closed door at `pos`, using a key from the inventory if needed
+    pub fn try_open_door(&mut self, pos: &Pos, door_id: usize) {
+        let door = match self.get_door_props(door_id) {
+            Some(v) => v,
+            None => {
+                log::warn!("door {} not found", door_id);
+                return;
+            }
+        };
+
+        if door
+            .flag
+            .as_ref()
+            .is_some_and(|flag| self.flags.is_set(flag))
+        {
+            self.set_map_block(pos, MapBlockTypes::Door(door_id, true));
+            self.info_queue
+                .queue("Door".to_string(), "The door is open".to_string());
+            return;
+        }
+
+        let key_index = door.key.and_then(|key| self.inventory.position_of(key));
+        match key_index {
+            Some(index) => {
+                let key_name = self.inventory.inventory[index].props.name.clone();
+                if door.consume_key {
+                    self.inventory.remove_item(index);
+                }
+                self.set_map_block(pos, MapBlockTypes::Door(door_id, true));
+                self.info_queue.queue(
+                    "Door".to_string(),
+                    format!("You unlocked the door with the {}", key_name),
+                );
+            }
+            None => self
+                .info_queue
+                .queue("Door".to_string(), "The door is locked".to_string()),
+        }
+    }
+
+    pub fn pull_lever(&mut self, pos: &Pos, lever_id: usize) {
+        let lever = match self.levers.iter().find(|lever| lever.id == lever_id) {
+            Some(v) => v.clone(),
+            None => {
+                log::warn!("lever {} not found", lever_id);
+                return;
+            }
+        };
+        self.set_map_block(pos, MapBlockTypes::Lever(lever_id, true));
+        self.info_queue
+            .queue("Lever".to_string(), "You pulled the lever".to_string());
+        self.set_flag(lever.flag);
+    }
+
+    /// opens every door on every map waiting for the flag once it is set
+    pub fn open_doors_with_flag(&mut self, flag: &str) {
+        if !self.flags.is_set(flag) {
+            return;
+        }
+        let door_ids: Vec<usize> = self
+            .doors
+            .iter()
+            .filter(|door| door.flag.as_deref() == Some(flag))
+            .map(|door| door.id)
+            .collect();
+
+        for map in self.maps.iter_mut() {
+            for block in map.iter_mut().flatten() {
+                if let MapBlockTypes::Door(door_id, false) = block {
+                    if door_ids.contains(door_id) {
+                        *block = MapBlockTypes::Door(*door_id, true);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, MapBlockTypes, Pos};
+
+    fn door_game(door: &str) -> Game {
+        Game::from_yaml(&format!(
+            "
+maps:
+    - '|_|_|_| |_|D0|L0|'
+doors:
+    - {}
+levers:
+    - {{id: 0, flag: gate}}
+",
+            door
+        ))
+    }
+
+    #[test]
+    fn test_door_with_key() {
+        let mut game = door_game("{id: 0, key: 1, consume_key: true}");
+        let pos = Pos { i: 1, j: 1 };
+
+        game.try_open_door(&pos, 0);
+        assert_eq!(game.maps[game.cur_map][1][1], MapBlockTypes::Door(0, false));
+
+        game.inventory.add_item(1, &game.global_items).unwrap();
+        game.try_open_door(&pos, 0);
+        assert_eq!(game.maps[game.cur_map][1][1], MapBlockTypes::Door(0, true));
+        assert_eq!(game.inventory.inventory.len(), 0);
+    }
+
+    #[test]
+    fn test_door_with_lever() {
+        let mut game = door_game("{id: 0, flag: gate}");
+
+        game.pull_lever(&Pos { i: 2, j: 1 }, 0);
+
+        assert!(game.flags.is_set("gate"));
+        assert_eq!(game.maps[game.cur_map][1][1], MapBlockTypes::Door(0, true));
+        assert_eq!(game.maps[game.cur_map][1][2], MapBlockTypes::Lever(0, true));
+    }
+}
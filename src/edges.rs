@@ -0,0 +1,358 @@
+use crate::game::{Game, MapBlockTypes, Pos};
+use crate::map_gen::{Map, MapDefinition, MapRef, Names};
+use serde::Deserialize;
+use std::collections::HashMap;
+use thiserror::Error;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    North,
+    South,
+    West,
+    East,
+}
+
+impl Side {
+    pub const ALL: [Side; 4] = [Side::North, Side::South, Side::West, Side::East];
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Side::North => "north",
+            Side::South => "south",
+            Side::West => "west",
+            Side::East => "east",
+        };
+        f.write_str(name)
+    }
+}
+
+/// the map behind a side of another map, as written in the scenario
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct EdgeDefinition {
+    pub map: MapRef,
+    /// added to the column (north and south) or row (west and east) when crossing
+    #[serde(default)]
+    pub offset: isize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub map: usize,
+    pub offset: isize,
+}
+
+pub type Edges = HashMap<Side, Edge>;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum EdgeError {
+    #[error("the {side} side of map {map} leads to map '{target}', which does not exist")]
+    UnknownMap {
+        map: usize,
+        side: Side,
+        target: String,
+    },
+    #[error("walking off the {side} side of map {map} at row {j}, tile {i} leads outside of map {target}")]
+    OutOfBounds {
+        map: usize,
+        side: Side,
+        j: usize,
+        i: usize,
+        target: usize,
+    },
+    #[error("walking off the {side} side of map {map} at row {j}, tile {i} leads into a wall on map {target}")]
+    IntoWall {
+        map: usize,
+        side: Side,
+        j: usize,
+        i: usize,
+        target: usize,
+    },
+}
+
+/// tiles of a map on one of its sides
+pub fn side_positions(map: &Map, side: Side) -> Vec<Pos> {
+    let mut positions = vec![];
+    for (j, row) in map.iter().enumerate() {
+        for i in 0..row.len() {
+            let pos = Pos { i, j };
+            if on_side(map, &pos, side) {
+                positions.push(pos);
+            }
+        }
+    }
+    positions
+}
+
+fn on_side(map: &Map, pos: &Pos, side: Side) -> bool {
+    match side {
+        Side::North => pos.j == 0,
+        Side::South => pos.j + 1 == map.len(),
+        Side::West => pos.i == 0,
+        Side::East => map.get(pos.j).is_some_and(|row| pos.i + 1 == row.len()),
+    }
+}
+
+/// where a player standing at `pos` ends up when walking off `side`,
+/// None if the map has no edge there or it leads outside of the other map or into a wall
+pub fn edge_entry(
+    maps: &[Map],
+    edges: &[Edges],
+    map: usize,
+    pos: &Pos,
+    side: Side,
+) -> Option<(usize, Pos)> {
+    edge_target(maps, edges, map, pos, side)
+        .filter(|(target, entry)| maps[*target][entry.j][entry.i] != MapBlockTypes::NotWalkable)
+}
+
+/// the tile behind `side`, whether it can be walked on or not
+fn edge_target(
+    maps: &[Map],
+    edges: &[Edges],
+    map: usize,
+    pos: &Pos,
+    side: Side,
+) -> Option<(usize, Pos)> {
+    let edge = edges.get(map)?.get(&side)?;
+    if !on_side(maps.get(map)?, pos, side) {
+        return None;
+    }
+    let target = maps.get(edge.map)?;
+    let shift = |value: usize| usize::try_from(value as isize + edge.offset).ok();
+    let entry = match side {
+        Side::North => Pos {
+            i: shift(pos.i)?,
+            j: target.len().checked_sub(1)?,
+        },
+        Side::South => Pos {
+            i: shift(pos.i)?,
+            j: 0,
+        },
+        Side::West => {
+            let j = shift(pos.j)?;
+            Pos {
+                i: target.get(j)?.len().checked_sub(1)?,
+                j,
+            }
+        }
+        Side::East => Pos {
+            i: 0,
+            j: shift(pos.j)?,
+        },
+    };
+    target.get(entry.j)?.get(entry.i)?;
+    Some((edge.map, entry))
+}
+
+/// resolves the edges of all maps and collects every problem with them,
+/// edges to maps which do not exist are left out
+pub fn collect_edges(definitions: &[MapDefinition], maps: &[Map]) -> (Vec<Edges>, Vec<EdgeError>) {
+    let names = Names::of_maps(definitions);
+    let mut errors = vec![];
+    let mut all_edges = vec![];
+    for (m, definition) in definitions.iter().enumerate() {
+        let mut edges = Edges::new();
+        for side in Side::ALL {
+            let edge = match definition.edges.get(&side) {
+                Some(v) => v,
+                None => continue,
+            };
+            let target = match &edge.map {
+                MapRef::Index(index) if *index < maps.len() => Some(*index),
+                MapRef::Index(_) => None,
+                MapRef::Name(name) => names.map(name).ok().filter(|index| *index < maps.len()),
+            };
+            match target {
+                Some(target) => {
+                    edges.insert(
+                        side,
+                        Edge {
+                            map: target,
+                            offset: edge.offset,
+                        },
+                    );
+                }
+                None => errors.push(EdgeError::UnknownMap {
+                    map: m,
+                    side,
+                    target: match &edge.map {
+                        MapRef::Index(index) => index.to_string(),
+                        MapRef::Name(name) => name.clone(),
+                    },
+                }),
+            }
+        }
+        all_edges.push(edges);
+    }
+
+    for (m, map) in maps.iter().enumerate() {
+        for side in Side::ALL {
+            let target = match all_edges[m].get(&side) {
+                Some(edge) => edge.map,
+                None => continue,
+            };
+            for pos in side_positions(map, side) {
+                if map[pos.j][pos.i] == MapBlockTypes::NotWalkable {
+                    continue;
+                }
+                match edge_target(maps, &all_edges, m, &pos, side) {
+                    None => errors.push(EdgeError::OutOfBounds {
+                        map: m,
+                        side,
+                        j: pos.j,
+                        i: pos.i,
+                        target,
+                    }),
+                    Some((_, entry))
+                        if maps[target][entry.j][entry.i] == MapBlockTypes::NotWalkable =>
+                    {
+                        errors.push(EdgeError::IntoWall {
+                            map: m,
+                            side,
+                            j: pos.j,
+                            i: pos.i,
+                            target,
+                        })
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+    (all_edges, errors)
+}
+
+impl Game {
+    /// walks off a side of the current map if it has an edge there, returns whether it did
+    pub fn cross_edge(&mut self, side: Side) -> bool {
+        match edge_entry(&self.maps, &self.edges, self.cur_map, &self.pos, side) {
+            Some((map, pos)) => {
+                self.enter_map(map, pos);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// like collect_edges, but stops at the first problem
+pub fn resolve_edges(definitions: &[MapDefinition], maps: &[Map]) -> Result<Vec<Edges>, EdgeError> {
+    let (edges, mut errors) = collect_edges(definitions, maps);
+    if errors.is_empty() {
+        Ok(edges)
+    } else {
+        Err(errors.remove(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::edges::{edge_entry, resolve_edges, EdgeError, Side};
+    use crate::game::{Movement, Pos};
+    use crate::map_gen::{generate_maps, Legend, MapDefinition};
+
+    fn definitions(yaml: &str) -> Vec<MapDefinition> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_edge_entry() {
+        let definitions = definitions(
+            "- name: hall
+  tiles: '|_|_|_| |_|_|_|'
+  edges:
+      east: {map: cellar, offset: 1}
+- name: cellar
+  tiles: '|x|x| |_|x| |_|_|'
+  edges:
+      west: {map: 0, offset: -1}",
+        );
+        let maps = generate_maps(&definitions, &Legend::new()).unwrap();
+        let edges = resolve_edges(&definitions, &maps).unwrap();
+
+        assert_eq!(
+            edge_entry(&maps, &edges, 0, &Pos { i: 2, j: 1 }, Side::East)
+                .map(|(m, p)| (m, p.j, p.i)),
+            Some((1, 2, 0))
+        );
+        assert_eq!(
+            edge_entry(&maps, &edges, 1, &Pos { i: 0, j: 1 }, Side::West)
+                .map(|(m, p)| (m, p.j, p.i)),
+            Some((0, 0, 2))
+        );
+        // not standing on the side
+        assert!(edge_entry(&maps, &edges, 0, &Pos { i: 1, j: 1 }, Side::East).is_none());
+        assert!(edge_entry(&maps, &edges, 0, &Pos { i: 0, j: 0 }, Side::West).is_none());
+    }
+
+    #[test]
+    fn test_edge_errors() {
+        let definitions = definitions(
+            "- tiles: '|_|_| |_|_|'
+  edges:
+      south: {map: 1, offset: 1}
+      north: {map: crypt}
+- '|_|_|'",
+        );
+        let maps = generate_maps(&definitions, &Legend::new()).unwrap();
+
+        assert_eq!(
+            resolve_edges(&definitions, &maps),
+            Err(EdgeError::UnknownMap {
+                map: 0,
+                side: Side::North,
+                target: "crypt".to_string()
+            })
+        );
+        let (_, errors) = crate::edges::collect_edges(&definitions, &maps);
+        assert_eq!(
+            errors.last().unwrap().to_string(),
+            "walking off the south side of map 0 at row 1, tile 1 leads outside of map 1"
+        );
+    }
+
+    #[test]
+    fn test_edge_into_wall() {
+        let definitions = definitions(
+            "- tiles: '|_|_| |_|_|'
+  edges:
+      east: {map: 1}
+- '|x|_| |_|_|'",
+        );
+        let maps = generate_maps(&definitions, &Legend::new()).unwrap();
+
+        assert_eq!(
+            resolve_edges(&definitions, &maps),
+            Err(EdgeError::IntoWall {
+                map: 0,
+                side: Side::East,
+                j: 0,
+                i: 1,
+                target: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_cross_edge() {
+        let mut game = crate::game::Game::from_yaml(
+            "
+maps:
+    - tiles: '|_|_| |_|_|'
+      edges:
+          west: {map: 1}
+    - '|_|_|_| |_|_|_|'
+start_pos: [0, 1]
+",
+        );
+
+        game.west();
+
+        assert_eq!(game.cur_map, 1);
+        assert_eq!((game.pos.j, game.pos.i), (1, 2));
+    }
+}
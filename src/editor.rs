@@ -0,0 +1,816 @@
+use crate::custom_layer::ScenarioError;
+use crate::game::{MapBlockTypes, Pos};
+use crate::map_gen::{parse_raw_tile, Layout, RawTile};
+use crate::validate::{validate_scenario, Diagnostic, Severity};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+/// tiles which can be painted, numbered ones get the number of the brush appended
+pub const BRUSHES: [(&str, &str); 17] = [
+    ("wall", "x"),
+    ("path", "_"),
+    ("item", "I"),
+    ("enemy", "E"),
+    ("gold", "G"),
+    ("merchant", "S"),
+    ("door", "D"),
+    ("lever", "L"),
+    ("chest", "C"),
+    ("trap", "P"),
+    ("shrine", "H"),
+    ("npc", "N"),
+    ("readable", "R"),
+    ("conditional", "?"),
+    ("new map", "M"),
+    ("stairs down", ">"),
+    ("stairs up", "<"),
+];
+
+/// the teleport brush comes after the ones in BRUSHES
+const TELEPORT_BRUSH: usize = BRUSHES.len();
+
+/// a map being edited, its tiles are kept in the pipe syntax
+#[derive(Debug, Clone)]
+pub struct EditorMap {
+    pub tiles: Vec<Vec<String>>,
+    pub kind: MapKind,
+    /// only changed maps are written back, the others keep their formatting
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum MapKind {
+    Pipes,
+    /// characters of the grid by the tile they stand for
+    Grid(HashMap<String, char>),
+    /// made in tiled, the file can only be changed there
+    Tiled(String),
+    /// made by a generator, only its settings can be changed
+    Generated,
+    /// added in the editor
+    New,
+}
+
+pub struct Editor {
+    pub scenario_path: String,
+    /// the scenario file as it was read, the maps are written into it when saving
+    contents: String,
+    pub maps: Vec<EditorMap>,
+    pub cur_map: usize,
+    pub cursor: Pos,
+    /// index in BRUSHES or TELEPORT_BRUSH
+    pub brush: usize,
+    pub number: usize,
+    /// map, j and i of the teleport brush
+    pub teleport_target: Option<(usize, usize, usize)>,
+    /// a tile typed in by hand, used instead of the brush
+    pub custom: Option<String>,
+    /// text typed so far while entering a custom tile
+    pub input: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    pub unsaved: bool,
+    pub message: String,
+    quit_requested: bool,
+}
+
+impl Editor {
+    pub fn open(path: &str) -> Result<Editor, ScenarioError> {
+        let contents = std::fs::read_to_string(path).map_err(|source| ScenarioError::Read {
+            path: path.to_string(),
+            source,
+        })?;
+        let settings = crate::custom_layer::parse_game_settings(path)?;
+
+        let mut maps = vec![];
+        for (m, definition) in settings.maps.iter().enumerate() {
+            let map = match &definition.layout {
+                Layout::Pipes(map_str) => EditorMap {
+                    tiles: crate::map_gen::pipe_tokens(map_str),
+                    kind: MapKind::Pipes,
+                    changed: false,
+                },
+                Layout::Grid(grid) => {
+                    let legend = definition.legend(&settings.legend);
+                    let mut chars = HashMap::new();
+                    let mut tiles = vec![];
+                    for row in crate::map_gen::grid_rows(grid) {
+                        let mut tokens = vec![];
+                        for c in row.chars() {
+                            // characters which are not understood are kept, validation reports them
+                            let token = crate::map_gen::parse_grid_tile(c, &legend)
+                                .map_or(c.to_string(), |tile| tile.text());
+                            chars.entry(token.clone()).or_insert(c);
+                            tokens.push(token);
+                        }
+                        tiles.push(tokens);
+                    }
+                    let mut legend: Vec<_> = legend.iter().collect();
+                    legend.sort_by_key(|(c, _)| **c);
+                    for (c, tile) in legend {
+                        if let Ok(raw) = tile.raw() {
+                            chars.entry(raw.text()).or_insert(*c);
+                        }
+                    }
+                    chars.entry("x".to_string()).or_insert('#');
+                    chars.entry("_".to_string()).or_insert('.');
+                    chars.entry(">".to_string()).or_insert('>');
+                    chars.entry("<".to_string()).or_insert('<');
+                    EditorMap {
+                        tiles,
+                        kind: MapKind::Grid(chars),
+                        changed: false,
+                    }
+                }
+                Layout::Tiled(tiled) => EditorMap {
+                    tiles: tiled
+                        .cells
+                        .iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|cell| {
+                                    cell.raw(&tiled.tileset)
+                                        .map_or("x".to_string(), |tile| tile.text())
+                                })
+                                .collect()
+                        })
+                        .collect(),
+                    kind: MapKind::Tiled(tiled.file.clone()),
+                    changed: false,
+                },
+                // a generator which fails is shown empty, validation reports why
+                Layout::Generated(generator) => EditorMap {
+                    tiles: generator
+                        .generate(m)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(|row| row.iter().map(RawTile::text).collect())
+                        .collect(),
+                    kind: MapKind::Generated,
+                    changed: false,
+                },
+            };
+            maps.push(map);
+        }
+
+        let mut editor = Editor {
+            scenario_path: path.to_string(),
+            contents,
+            maps,
+            cur_map: 0,
+            cursor: Pos { i: 0, j: 0 },
+            brush: 0,
+            number: 0,
+            teleport_target: None,
+            custom: None,
+            input: None,
+            diagnostics: vec![],
+            unsaved: false,
+            message: String::new(),
+            quit_requested: false,
+        };
+        if editor.maps.is_empty() {
+            editor.new_map();
+            editor.unsaved = false;
+        }
+        editor.validate();
+        Ok(editor)
+    }
+
+    pub fn brush_name(&self) -> String {
+        if let Some(custom) = &self.custom {
+            return format!("tile {}", custom);
+        }
+        match BRUSHES.get(self.brush) {
+            Some((name, _)) => match self.brush_text() {
+                Some(text) if text.len() > 1 => format!("{} {}", name, text),
+                _ => name.to_string(),
+            },
+            None => match self.teleport_target {
+                Some((map, j, i)) => format!("teleport to map {}, row {}, tile {}", map, j, i),
+                None => "teleport, press t on the target first".to_string(),
+            },
+        }
+    }
+
+    /// the tile the brush paints, None for a teleport without a target
+    pub fn brush_text(&self) -> Option<String> {
+        if let Some(custom) = &self.custom {
+            return Some(custom.clone());
+        }
+        match BRUSHES.get(self.brush) {
+            Some((_, "x" | "_" | ">" | "<")) => Some(BRUSHES[self.brush].1.to_string()),
+            Some((_, prefix)) => Some(format!("{}{}", prefix, self.number)),
+            None => self
+                .teleport_target
+                .map(|(map, j, i)| format!("T({},{},{})", map, j, i)),
+        }
+    }
+
+    pub fn next_brush(&mut self) {
+        self.custom = None;
+        self.brush = (self.brush + 1) % (TELEPORT_BRUSH + 1);
+    }
+
+    pub fn previous_brush(&mut self) {
+        self.custom = None;
+        self.brush = (self.brush + TELEPORT_BRUSH) % (TELEPORT_BRUSH + 1);
+    }
+
+    pub fn number_up(&mut self) {
+        self.number += 1;
+    }
+
+    pub fn number_down(&mut self) {
+        self.number = self.number.saturating_sub(1);
+    }
+
+    /// the tile under the cursor becomes the target of the teleport brush
+    pub fn mark_teleport_target(&mut self) {
+        self.teleport_target = Some((self.cur_map, self.cursor.j, self.cursor.i));
+        self.custom = None;
+        self.brush = TELEPORT_BRUSH;
+    }
+
+    /// takes the tile under the cursor as the brush
+    pub fn pick(&mut self) {
+        if let Some(tile) = self.tile() {
+            self.custom = Some(tile.clone());
+        }
+    }
+
+    pub fn tile(&self) -> Option<&String> {
+        self.maps
+            .get(self.cur_map)?
+            .tiles
+            .get(self.cursor.j)?
+            .get(self.cursor.i)
+    }
+
+    pub fn start_input(&mut self) {
+        self.input = Some(String::new());
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        if let Some(input) = &mut self.input {
+            input.push(c);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(input) = &mut self.input {
+            input.pop();
+        }
+    }
+
+    pub fn cancel_input(&mut self) {
+        self.input = None;
+    }
+
+    /// the typed tile becomes the brush if it is understood
+    pub fn confirm_input(&mut self) {
+        let text = match self.input.take() {
+            Some(text) => text.trim().to_string(),
+            None => return,
+        };
+        match parse_raw_tile(&text) {
+            Ok(_) => {
+                self.message = format!("painting {}", text);
+                self.custom = Some(text);
+            }
+            Err(e) => self.message = e.to_string(),
+        }
+    }
+
+    pub fn move_cursor(&mut self, dj: isize, di: isize) {
+        let map = &self.maps[self.cur_map];
+        let rows = map.tiles.len();
+        let columns = map.tiles.get(self.cursor.j).map_or(0, |row| row.len());
+        let j = self.cursor.j as isize + dj;
+        let i = self.cursor.i as isize + di;
+        if j >= 0 && (j as usize) < rows {
+            self.cursor.j = j as usize;
+        }
+        if i >= 0 && (i as usize) < columns {
+            self.cursor.i = i as usize;
+        }
+        self.clamp_cursor();
+    }
+
+    fn clamp_cursor(&mut self) {
+        let map = &self.maps[self.cur_map];
+        self.cursor.j = self.cursor.j.min(map.tiles.len().saturating_sub(1));
+        let columns = map.tiles.get(self.cursor.j).map_or(0, |row| row.len());
+        self.cursor.i = self.cursor.i.min(columns.saturating_sub(1));
+    }
+
+    pub fn next_map(&mut self) {
+        self.cur_map = (self.cur_map + 1) % self.maps.len();
+        self.clamp_cursor();
+    }
+
+    pub fn previous_map(&mut self) {
+        self.cur_map = (self.cur_map + self.maps.len() - 1) % self.maps.len();
+        self.clamp_cursor();
+    }
+
+    /// adds a map of walls the size of the current one
+    pub fn new_map(&mut self) {
+        let (rows, columns) = match self.maps.get(self.cur_map) {
+            Some(map) => (
+                map.tiles.len().max(1),
+                map.tiles.first().map_or(1, |row| row.len().max(1)),
+            ),
+            None => (10, 10),
+        };
+        self.maps.push(EditorMap {
+            tiles: vec![vec!["x".to_string(); columns]; rows],
+            kind: MapKind::New,
+            changed: true,
+        });
+        self.cur_map = self.maps.len() - 1;
+        self.cursor = Pos { i: 0, j: 0 };
+        self.changed();
+    }
+
+    /// maps made in tiled can not be changed here
+    fn editable(&mut self) -> bool {
+        match &self.maps[self.cur_map].kind {
+            MapKind::Tiled(file) => {
+                self.message = format!(
+                    "map {} is made in tiled, change {} there",
+                    self.cur_map, file
+                );
+                false
+            }
+            MapKind::Generated => {
+                self.message = format!(
+                    "map {} is generated, change its settings in the scenario",
+                    self.cur_map
+                );
+                false
+            }
+            _ => true,
+        }
+    }
+
+    pub fn paint(&mut self) {
+        if !self.editable() {
+            return;
+        }
+        let text = match self.brush_text() {
+            Some(text) => text,
+            None => {
+                self.message = "press t on the tile the teleport leads to first".to_string();
+                return;
+            }
+        };
+        let Pos { i, j } = self.cursor;
+        let map = &mut self.maps[self.cur_map];
+        if let Some(tile) = map.tiles.get_mut(j).and_then(|row| row.get_mut(i)) {
+            if *tile != text {
+                *tile = text;
+                map.changed = true;
+                self.changed();
+            }
+        }
+    }
+
+    /// adds (`by` 1) or removes (`by` -1) a row at the bottom of the map
+    pub fn resize_rows(&mut self, by: isize) {
+        if !self.editable() {
+            return;
+        }
+        let map = &mut self.maps[self.cur_map];
+        if by > 0 {
+            let columns = map.tiles.first().map_or(1, |row| row.len());
+            map.tiles.push(vec!["x".to_string(); columns]);
+        } else if map.tiles.len() > 1 {
+            map.tiles.pop();
+        } else {
+            return;
+        }
+        map.changed = true;
+        self.clamp_cursor();
+        self.changed();
+    }
+
+    /// adds (`by` 1) or removes (`by` -1) a column on the right of the map
+    pub fn resize_columns(&mut self, by: isize) {
+        if !self.editable() {
+            return;
+        }
+        let map = &mut self.maps[self.cur_map];
+        if by < 0 && map.tiles.iter().any(|row| row.len() <= 1) {
+            return;
+        }
+        for row in &mut map.tiles {
+            if by > 0 {
+                row.push("x".to_string());
+            } else {
+                row.pop();
+            }
+        }
+        map.changed = true;
+        self.clamp_cursor();
+        self.changed();
+    }
+
+    fn changed(&mut self) {
+        self.unsaved = true;
+        self.quit_requested = false;
+        self.validate();
+    }
+
+    /// checks the scenario as it would be saved
+    pub fn validate(&mut self) {
+        match self.scenario_text() {
+            Ok(contents) => {
+                let dir = Path::new(&self.scenario_path)
+                    .parent()
+                    .unwrap_or(Path::new(""));
+                self.diagnostics = validate_scenario(&contents, dir);
+            }
+            Err(e) => {
+                self.diagnostics = vec![];
+                self.message = e;
+            }
+        }
+    }
+
+    pub fn errors(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .count()
+    }
+
+    pub fn save(&mut self) {
+        let contents = match self.scenario_text() {
+            Ok(contents) => contents,
+            Err(e) => {
+                self.message = e;
+                return;
+            }
+        };
+        if let Err(e) = crate::save::write_atomic(Path::new(&self.scenario_path), &contents) {
+            self.message = e;
+            return;
+        }
+        self.contents = contents;
+        for map in &mut self.maps {
+            map.changed = false;
+            if let MapKind::New = map.kind {
+                map.kind = MapKind::Pipes;
+            }
+        }
+        self.unsaved = false;
+        self.message = match self.errors() {
+            0 => format!("saved {}", self.scenario_path),
+            errors => format!("saved {} with {} errors", self.scenario_path, errors),
+        };
+    }
+
+    /// whether the editor can be closed, unsaved changes need a second try
+    pub fn quit(&mut self) -> bool {
+        if self.unsaved && !self.quit_requested {
+            self.quit_requested = true;
+            self.message = "there are unsaved changes, press q again to quit anyway".to_string();
+            return false;
+        }
+        true
+    }
+
+    /// the scenario file with the changed maps written into it
+    pub fn scenario_text(&self) -> Result<String, String> {
+        let lines: Vec<&str> = self.contents.lines().collect();
+        let entries = map_entries(&lines);
+        let existing = self
+            .maps
+            .iter()
+            .filter(|map| !matches!(map.kind, MapKind::New))
+            .count();
+        if entries.len() != existing {
+            return Err(
+                "the maps of this scenario can not be written back, write them as a list with one map per `- `"
+                    .to_string(),
+            );
+        }
+
+        let mut new_lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        let mut added = vec![];
+        let dash_indent = entries
+            .first()
+            .map_or(4, |entry| indent(lines[entry.start]));
+        for map in self
+            .maps
+            .iter()
+            .filter(|map| matches!(map.kind, MapKind::New))
+        {
+            added.push(format!("{}- >-", " ".repeat(dash_indent)));
+            added.extend(pipe_rows(&map.tiles, dash_indent + 4));
+        }
+        match entries.last() {
+            Some(entry) => {
+                let end = entry.end;
+                new_lines.splice(end..end, added);
+            }
+            None if !added.is_empty() => {
+                let maps_line = new_lines
+                    .iter()
+                    .position(|line| line.starts_with("maps:"))
+                    .ok_or("the scenario has no maps")?;
+                new_lines[maps_line] = "maps:".to_string();
+                new_lines.splice(maps_line + 1..maps_line + 1, added);
+            }
+            None => {}
+        }
+
+        // from the back, so the lines of the maps before stay where they are
+        for (m, entry) in entries.iter().enumerate().rev() {
+            let map = &self.maps[m];
+            if map.changed {
+                let (range, layout) = write_layout(&lines, entry.clone(), map);
+                new_lines.splice(range, layout);
+            }
+        }
+
+        let mut text = new_lines.join("\n");
+        if self.contents.ends_with('\n') {
+            text.push('\n');
+        }
+        Ok(text)
+    }
+}
+
+/// how a tile is drawn in the editor, unlike in the game every tile can be told apart
+pub fn tile_symbol(text: &str) -> &'static str {
+    match parse_raw_tile(text) {
+        Ok(RawTile::Block(MapBlockTypes::NewMapTrigger(_))) | Ok(RawTile::NewMapTo(_)) => "MM",
+        Ok(RawTile::Block(MapBlockTypes::Trap(_, _))) => "^^",
+        Ok(RawTile::Block(MapBlockTypes::FlagTrigger(_))) => "FF",
+        Ok(RawTile::Block(block)) => crate::map_gen::block_symbol(&block),
+        Ok(RawTile::Anchor(_)) => "AA",
+        Ok(RawTile::TeleportTo { .. }) => "TT",
+        Err(_) => "~~",
+    }
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+fn pipe_rows(tiles: &[Vec<String>], indent: usize) -> Vec<String> {
+    tiles
+        .iter()
+        .map(|row| format!("{}|{}|", " ".repeat(indent), row.join("|")))
+        .collect()
+}
+
+/// lines of each entry of the `maps` list, without the empty lines at its end
+fn map_entries(lines: &[&str]) -> Vec<Range<usize>> {
+    let mut entries: Vec<Range<usize>> = vec![];
+    let mut in_maps = false;
+    let mut dash_indent = None;
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if !line.starts_with(' ') && !line.starts_with('-') {
+            in_maps = line.starts_with("maps:");
+            continue;
+        }
+        if !in_maps {
+            continue;
+        }
+        if trimmed.starts_with("- ") || trimmed == "-" {
+            let dash = *dash_indent.get_or_insert(indent(line));
+            if indent(line) == dash {
+                entries.push(index..index + 1);
+                continue;
+            }
+        }
+        if let Some(entry) = entries.last_mut() {
+            entry.end = index + 1;
+        }
+    }
+    entries
+}
+
+/// the lines of a map entry which hold its tiles and what to put there instead
+fn write_layout(
+    lines: &[&str],
+    entry: Range<usize>,
+    map: &EditorMap,
+) -> (Range<usize>, Vec<String>) {
+    let mut key = None;
+    for index in entry.clone() {
+        let line = lines[index];
+        let mut rest = line.trim_start();
+        if index == entry.start {
+            rest = rest.trim_start_matches('-').trim_start();
+        }
+        if rest.starts_with("tiles:") || rest.starts_with("grid:") {
+            key = Some((index, line.len() - rest.len()));
+            break;
+        }
+    }
+
+    let grid = match &map.kind {
+        MapKind::Grid(chars) => grid_rows(&map.tiles, chars),
+        _ => None,
+    };
+    match key {
+        Some((index, column)) => {
+            // the value goes on until a line which is not indented more than the key
+            let mut end = index + 1;
+            for (next, line) in lines.iter().enumerate().take(entry.end).skip(index + 1) {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                if indent(line) <= column {
+                    break;
+                }
+                end = next + 1;
+            }
+            let prefix = &lines[index][..column];
+            let mut layout = vec![];
+            match grid {
+                Some(rows) => {
+                    layout.push(format!("{}grid: |", prefix));
+                    layout.extend(
+                        rows.iter()
+                            .map(|row| format!("{}{}", " ".repeat(column + 2), row)),
+                    );
+                }
+                None => {
+                    layout.push(format!("{}tiles: >-", prefix));
+                    layout.extend(pipe_rows(&map.tiles, column + 2));
+                }
+            }
+            (index..end, layout)
+        }
+        // a plain string is the map itself
+        None => {
+            let dash = indent(lines[entry.start]);
+            let mut layout = vec![format!("{}- >-", " ".repeat(dash))];
+            layout.extend(pipe_rows(&map.tiles, dash + 4));
+            (entry, layout)
+        }
+    }
+}
+
+/// the rows of a grid map, None if a tile has no character
+fn grid_rows(tiles: &[Vec<String>], chars: &HashMap<String, char>) -> Option<Vec<String>> {
+    tiles
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|tile| chars.get(tile).copied().filter(|c| *c != ' '))
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::editor::{map_entries, Editor, TELEPORT_BRUSH};
+    use crate::game::Pos;
+
+    const SCENARIO: &str = "maps: !!seq
+    # the hall
+    - >-
+        |x|_|
+        |x|_|
+    - name: cellar
+      grid: |
+        #o
+        ..
+      legend:
+        'o': !item 1
+    - name: yard
+      tiles: '|_|_|'
+start_pos: [1, 0]
+";
+
+    fn editor(name: &str, contents: &str) -> (Editor, std::path::PathBuf) {
+        let path =
+            std::env::temp_dir().join(format!("editor-{}-{}.yaml", name, std::process::id()));
+        let scenario = std::fs::read_to_string("test.yaml").unwrap();
+        let rest = &scenario[scenario.find("start_pos:").unwrap()..];
+        let rest = &rest[rest.find('\n').unwrap() + 1..];
+        // the pit and the conditional teleport of the demo lead into its larger first map
+        let rest = rest
+            .replace("target: [0, 4, 4]", "target: [0, 1, 1]")
+            .replace("then: T(0,8,2)", "then: T(0,1,1)");
+        std::fs::write(&path, format!("{}{}", contents, rest)).unwrap();
+        (Editor::open(path.to_str().unwrap()).unwrap(), path)
+    }
+
+    #[test]
+    fn test_map_entries() {
+        let lines: Vec<&str> = SCENARIO.lines().collect();
+        assert_eq!(map_entries(&lines), vec![2..5, 5..11, 11..13]);
+    }
+
+    #[test]
+    fn test_open_and_paint() {
+        let (mut editor, path) = editor("paint", SCENARIO);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(editor.maps[1].tiles, vec![vec!["x", "I1"], vec!["_", "_"]]);
+        // nothing changed, nothing is rewritten
+        assert!(editor.scenario_text().unwrap().starts_with(SCENARIO));
+
+        editor.next_map();
+        editor.brush = 2;
+        editor.number = 1;
+        editor.cursor = Pos { i: 0, j: 1 };
+        editor.paint();
+        editor.next_map();
+        editor.mark_teleport_target();
+        editor.next_map();
+        assert_eq!(editor.brush, TELEPORT_BRUSH);
+        editor.paint();
+
+        let text = editor.scenario_text().unwrap();
+        assert!(text.starts_with(
+            "maps: !!seq
+    # the hall
+    - >-
+        |T(2,0,0)|_|
+        |x|_|
+    - name: cellar
+      grid: |
+        #o
+        o.
+      legend:
+        'o': !item 1
+    - name: yard
+      tiles: '|_|_|'
+start_pos: [1, 0]
+"
+        ));
+        assert!(editor.unsaved);
+    }
+
+    #[test]
+    fn test_resize_and_new_map() {
+        let (mut editor, path) = editor("resize", SCENARIO);
+
+        editor.cur_map = 2;
+        editor.resize_rows(1);
+        editor.resize_columns(1);
+        editor.cursor = Pos { i: 2, j: 1 };
+        editor.brush = 0;
+        editor.paint();
+        editor.cur_map = 1;
+        editor.resize_columns(-1);
+        editor.new_map();
+        assert_eq!(editor.maps[3].tiles.len(), 2);
+        editor.save();
+
+        assert!(!path.with_extension("tmp").exists());
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(text.contains(
+            "    - name: cellar
+      grid: |
+        #
+        .
+      legend:
+        'o': !item 1
+    - name: yard
+      tiles: >-
+        |_|_|x|
+        |x|x|x|
+    - >-
+        |x|
+        |x|
+start_pos: [1, 0]
+"
+        ));
+        assert!(!editor.unsaved);
+        assert_eq!(editor.errors(), 0, "{:?}", editor.diagnostics);
+    }
+
+    #[test]
+    fn test_live_validation() {
+        let (mut editor, path) = editor("validate", SCENARIO);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(editor.errors(), 0, "{:?}", editor.diagnostics);
+
+        editor.start_input();
+        for c in "E99".chars() {
+            editor.type_char(c);
+        }
+        editor.confirm_input();
+        editor.paint();
+
+        assert_eq!(editor.errors(), 1);
+        assert!(editor.diagnostics[0].message.contains("enemy 99"));
+        assert!(!editor.quit());
+        assert!(editor.quit());
+    }
+}
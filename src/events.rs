@@ -0,0 +1,336 @@
+use crate::game::{Game, Pos};
+use serde::Deserialize;
+
+/// how deep events may trigger further events, e.g. a teleport into another on_enter region
+const MAX_EVENT_DEPTH: usize = 8;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Condition {
+    pub flag: Option<String>,
+    pub not_flag: Option<String>,
+    /// item_id in global_items the player has to carry
+    pub item: Option<usize>,
+    /// gold the player needs at least
+    pub gold: Option<usize>,
+    /// counter which has to reach `at_least`, 1 if not set
+    pub counter: Option<String>,
+    pub at_least: Option<i32>,
+}
+
+/// written as yaml tags, e.g. `!give_item 3` or `!set_flag door_open`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Message(String),
+    /// written as `!teleport [map, j, i]`
+    Teleport(usize, usize, usize),
+    /// places a tile in the map syntax, written as `!spawn [map, j, i, E0]`
+    Spawn(usize, usize, usize, String),
+    GiveItem(usize),
+    TakeItem(usize),
+    GiveGold(usize),
+    TakeGold(usize),
+    SetFlag(String),
+    UnsetFlag(String),
+    /// written as `!add_counter [name, amount]`
+    AddCounter(String, i32),
+    StartQuest(usize),
+    /// enemy_id in enemies
+    StartFight(usize),
+    /// script_id in scripts
+    RunScript(usize),
+}
+
+/// written as yaml tags, e.g. `!on_pickup 3` or `!on_enter {map: 0, from: [2, 2], to: [4, 6]}`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub enum Trigger {
+    #[serde(rename = "on_enter")]
+    Enter(Area),
+    /// item_id in global_items
+    #[serde(rename = "on_pickup")]
+    Pickup(usize),
+    /// enemy_id in enemies
+    #[serde(rename = "on_defeat")]
+    Defeat(usize),
+    /// number of steps walked, events which do not fire once repeat every that many steps
+    #[serde(rename = "on_turn")]
+    Turn(usize),
+}
+
+/// tile `from` or the rectangle between `from` and `to`, given as [j, i]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Area {
+    pub map: usize,
+    pub from: [usize; 2],
+    pub to: Option<[usize; 2]>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Event {
+    pub trigger: Trigger,
+    #[serde(default)]
+    pub condition: Condition,
+    pub actions: Vec<Action>,
+    /// events only fire once unless set to false
+    #[serde(default = "default_once")]
+    pub once: bool,
+}
+
+fn default_once() -> bool {
+    true
+}
+
+impl Condition {
+    pub fn is_met(&self, game: &Game) -> bool {
+        self.flag
+            .as_ref()
+            .map_or(true, |flag| game.flags.is_set(flag))
+            && self
+                .not_flag
+                .as_ref()
+                .map_or(true, |flag| !game.flags.is_set(flag))
+            && self
+                .item
+                .map_or(true, |item| game.inventory.position_of(item).is_some())
+            && self.gold.map_or(true, |gold| game.gold >= gold)
+            && self.counter.as_ref().map_or(true, |counter| {
+                game.flags.get(counter) >= self.at_least.unwrap_or(1)
+            })
+    }
+}
+
+impl Area {
+    pub fn contains(&self, map: usize, pos: &Pos) -> bool {
+        let [from_j, from_i] = self.from;
+        let [to_j, to_i] = self.to.unwrap_or(self.from);
+        self.map == map
+            && (from_j.min(to_j)..=from_j.max(to_j)).contains(&pos.j)
+            && (from_i.min(to_i)..=from_i.max(to_i)).contains(&pos.i)
+    }
+}
+
+impl Event {
+    fn is_fired_by(&self, game: &Game, trigger: &Trigger) -> bool {
+        match (&self.trigger, trigger) {
+            (Trigger::Enter(area), Trigger::Enter(_)) => area.contains(game.cur_map, &game.pos),
+            (Trigger::Turn(turn), Trigger::Turn(_)) if self.once => game.turn >= *turn,
+            (Trigger::Turn(turn), Trigger::Turn(_)) => *turn > 0 && game.turn % *turn == 0,
+            (a, b) => a == b,
+        }
+    }
+}
+
+impl Game {
+    /// runs the actions of all events matching the trigger, on_enter and on_turn
+    /// events are matched against the current position and turn
+    pub fn fire_events(&mut self, trigger: Trigger) {
+        if self.event_depth >= MAX_EVENT_DEPTH {
+            log::warn!("events nested too deep, skipping {:?}", trigger);
+            return;
+        }
+
+        let matching: Vec<usize> = self
+            .events
+            .iter()
+            .enumerate()
+            .filter(|(index, event)| {
+                !self.fired_events.contains(index)
+                    && event.is_fired_by(self, &trigger)
+                    && event.condition.is_met(self)
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        self.event_depth += 1;
+        for index in matching {
+            if self.events[index].once {
+                self.fired_events.push(index);
+            }
+            for action in self.events[index].actions.clone() {
+                self.apply_action(&action);
+            }
+        }
+        self.event_depth -= 1;
+    }
+
+    pub fn apply_action(&mut self, action: &Action) {
+        match action {
+            Action::Message(message) => self.info_queue.queue("Info".to_string(), message.clone()),
+            Action::Teleport(map, j, i) if self.has_tile(*map, *j, *i) => {
+                self.enter_map(*map, Pos { i: *i, j: *j })
+            }
+            Action::Teleport(map, j, i) => {
+                log::warn!("can not teleport to {} {} {}", map, j, i)
+            }
+            Action::Spawn(map, j, i, block) => {
+                match self.maps.get_mut(*map).and_then(|map| map.get_mut(*j)) {
+                    Some(row) if *i < row.len() => row[*i] = crate::map_gen::get_block_type(block),
+                    _ => log::warn!("can not spawn {} at {} {} {}", block, map, j, i),
+                }
+            }
+            Action::GiveItem(item_id) => match self.pick_up_item(*item_id) {
+                Ok(()) => self.info_queue.queue(
+                    "Item".to_string(),
+                    "You have received a ".to_string()
+                        + &self.inventory.inventory.last().unwrap().props.name,
+                ),
+                Err(e) => log::warn!("{} (item {})", e, item_id),
+            },
+            Action::TakeItem(item_id) => {
+                if let Some(index) = self.inventory.position_of(*item_id) {
+                    let item = self.inventory.remove_item(index).unwrap();
+                    self.info_queue.queue(
+                        "Item".to_string(),
+                        "You have handed over a ".to_string() + &item.props.name,
+                    );
+                }
+            }
+            Action::GiveGold(amount) => {
+                self.gold = self.gold.saturating_add(*amount);
+                self.info_queue.queue(
+                    "Gold".to_string(),
+                    format!("You have received {} gold", amount),
+                );
+            }
+            Action::TakeGold(amount) => {
+                self.gold = self.gold.saturating_sub(*amount);
+                self.info_queue
+                    .queue("Gold".to_string(), format!("You have paid {} gold", amount));
+            }
+            Action::SetFlag(flag) => self.set_flag(flag.clone()),
+            Action::UnsetFlag(flag) => self.unset_flag(flag.clone()),
+            Action::AddCounter(counter, amount) => self.add_to_counter(counter.clone(), *amount),
+            Action::StartQuest(quest_id) => self.start_quest(*quest_id),
+            Action::StartFight(enemy_id) => self.start_encounter(*enemy_id),
+            Action::RunScript(script_id) => self.run_script(*script_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::events::{Action, Area, Trigger};
+    use crate::game::{Game, MapBlockTypes, Movement};
+
+    fn event_game(events: &str) -> Game {
+        Game::from_yaml(&format!("start_pos: [3, 3]\nevents:{}", events))
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let game = event_game(
+            "
+- trigger: !on_enter {map: 0, from: [3, 4], to: [5, 6]}
+  actions:
+      - !message You hear a noise
+      - !spawn [0, 4, 4, E0]
+- trigger: !on_turn 10
+  once: false
+  actions:
+      - !teleport [1, 2, 2]
+",
+        );
+        assert_eq!(
+            game.events[0].trigger,
+            Trigger::Enter(Area {
+                map: 0,
+                from: [3, 4],
+                to: Some([5, 6])
+            })
+        );
+        assert_eq!(
+            game.events[0].actions[1],
+            Action::Spawn(0, 4, 4, "E0".to_string())
+        );
+        assert!(!game.events[1].once);
+    }
+
+    #[test]
+    fn test_on_enter_event() {
+        let mut game = event_game(
+            "
+- trigger: !on_enter {map: 0, from: [3, 4]}
+  actions:
+      - !spawn [0, 3, 5, I0]
+      - !add_counter [entered, 1]
+",
+        );
+
+        game.east();
+        game.west();
+        game.east();
+
+        assert_eq!(game.maps[0][3][5], MapBlockTypes::ItemTrigger(0));
+        assert_eq!(game.flags.get("entered"), 1);
+    }
+
+    #[test]
+    fn test_on_pickup_teleport() {
+        let mut game = Game::from_yaml(
+            "
+maps:
+    - '|_|_|_|I0|'
+    - '|_|'
+start_pos: [2, 0]
+events:
+    - trigger: !on_pickup 0
+      actions:
+          - !teleport [1, 0, 0]
+",
+        );
+
+        game.east();
+
+        assert_eq!(game.cur_map, 1);
+        assert_eq!((game.pos.j, game.pos.i), (0, 0));
+        assert_eq!(game.inventory.inventory[0].props.id, 0);
+    }
+
+    #[test]
+    fn test_on_pickup_event_with_condition() {
+        let mut game = event_game(
+            "
+- trigger: !on_pickup 0
+  condition:
+      flag: ready
+  actions:
+      - !give_gold 5
+",
+        );
+
+        game.pick_up_item(0).unwrap();
+        assert_eq!(game.gold, 0);
+
+        game.set_flag("ready".to_string());
+        game.pick_up_item(0).unwrap();
+        assert_eq!(game.gold, 5);
+    }
+
+    #[test]
+    fn test_recursive_events_stop() {
+        let mut game = event_game(
+            "
+- trigger: !on_enter {map: 1, from: [2, 2]}
+  once: false
+  actions:
+      - !add_counter [loops, 1]
+      - !teleport [1, 2, 2]
+",
+        );
+
+        game.apply_action(&Action::Teleport(1, 2, 2));
+
+        assert_eq!(game.flags.get("loops"), super::MAX_EVENT_DEPTH as i32);
+    }
+
+    #[test]
+    fn test_teleport_outside_of_the_maps() {
+        let mut game = event_game(" []");
+
+        game.apply_action(&Action::Teleport(1, 6, 0));
+        game.apply_action(&Action::Teleport(2, 0, 0));
+
+        assert_eq!(game.cur_map, 0);
+        assert_eq!((game.pos.j, game.pos.i), (3, 3));
+    }
+}
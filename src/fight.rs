@@ -1,6 +1,7 @@
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Fight {
     /// all entities acting in the fight
     pub entities: Vec<crate::Entity>,
@@ -18,10 +19,9 @@ impl Fight {
         };
     }
 
-    pub fn ini(&mut self) {
+    pub fn ini(&mut self, rng: &mut impl Rng) {
         let mut inis = vec![];
         for _ in self.entities.iter() {
-            let mut rng = rand::thread_rng();
             inis.push(rng.gen_range(0, 12));
         }
         self.iniative = inis;
@@ -31,4 +31,38 @@ impl Fight {
     pub fn start(&mut self) -> () {
         self.round += 1;
     }
+
+    /// lets every entity still standing attack its opponent once, in order of the iniative
+    pub fn round(&mut self, rng: &mut impl Rng) -> Vec<String> {
+        self.start();
+        let mut order: Vec<usize> = (0..self.entities.len()).collect();
+        order.sort_by_key(|&index| std::cmp::Reverse(self.iniative.get(index).copied()));
+
+        let mut messages = vec![];
+        for attacker in order {
+            let defender = if attacker == 0 { 1 } else { 0 };
+            if self.entities[attacker].hp <= 0 || self.entities[defender].hp <= 0 {
+                continue;
+            }
+
+            let at = self.entities[attacker].at.unwrap_or(0) as i32;
+            let aw = self.entities[defender].aw.unwrap_or(0) as i32;
+            if rng.gen_range(1, 21) + at < 10 + aw {
+                messages.push(format!("{} misses", self.entities[attacker].name));
+                continue;
+            }
+
+            let damage = rng.gen_range(1, 7);
+            self.entities[defender].hp -= damage;
+            messages.push(format!(
+                "{} hits {} for {} damage",
+                self.entities[attacker].name, self.entities[defender].name, damage
+            ));
+        }
+        messages
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.entities.iter().any(|entity| entity.hp <= 0)
+    }
 }
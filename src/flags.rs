@@ -0,0 +1,205 @@
+use crate::events::Condition;
+use crate::game::{Game, MapBlockTypes};
+use crate::map_gen::{Names, RawTile, TileError};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+
+/// boolean flags and counters describing the state of a scenario,
+/// a flag is a counter which is set as soon as it is not 0
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Flags {
+    pub values: HashMap<String, i32>,
+}
+
+impl Flags {
+    pub fn set(&mut self, name: &str) {
+        self.values.insert(name.to_string(), 1);
+    }
+
+    pub fn unset(&mut self, name: &str) {
+        self.values.remove(name);
+    }
+
+    pub fn is_set(&self, name: &str) -> bool {
+        self.get(name) != 0
+    }
+
+    pub fn add(&mut self, name: &str, amount: i32) {
+        *self.values.entry(name.to_string()).or_insert(0) += amount;
+    }
+
+    pub fn get(&self, name: &str) -> i32 {
+        self.values.get(name).copied().unwrap_or(0)
+    }
+}
+
+/// tile which behaves like `then` while the condition is met and like `else` otherwise
+#[derive(Debug, Clone)]
+pub struct ConditionalTile {
+    pub id: usize,
+    pub condition: Condition,
+    pub then: MapBlockTypes,
+    pub otherwise: MapBlockTypes,
+}
+
+/// a conditional tile as it is written in the scenario, before the names in its tiles are resolved
+#[derive(Deserialize, Debug, Clone)]
+pub struct ConditionalTileDefinition {
+    pub id: usize,
+    pub condition: Condition,
+    /// tile in the map syntax, e.g. `_`, `T(1,2,3)` or `T(crypt.door)`
+    #[serde(deserialize_with = "deserialize_tile")]
+    pub then: RawTile,
+    #[serde(rename = "else", deserialize_with = "deserialize_tile")]
+    pub otherwise: RawTile,
+}
+
+impl ConditionalTileDefinition {
+    pub fn resolve(&self, names: &Names) -> Result<ConditionalTile, TileError> {
+        Ok(ConditionalTile {
+            id: self.id,
+            condition: self.condition.clone(),
+            then: self.then.resolve(names)?,
+            otherwise: self.otherwise.resolve(names)?,
+        })
+    }
+}
+
+fn deserialize_tile<'de, D: Deserializer<'de>>(deserializer: D) -> Result<RawTile, D::Error> {
+    let tile = String::deserialize(deserializer)?;
+    crate::map_gen::parse_raw_tile(&tile).map_err(serde::de::Error::custom)
+}
+
+impl Game {
+    /// sets a flag and opens every door waiting for it
+    pub fn set_flag(&mut self, flag: String) {
+        self.flags.set(&flag);
+        self.open_doors_with_flag(&flag);
+    }
+
+    pub fn unset_flag(&mut self, flag: String) {
+        self.flags.unset(&flag);
+    }
+
+    pub fn add_to_counter(&mut self, counter: String, amount: i32) {
+        self.flags.add(&counter, amount);
+        self.open_doors_with_flag(&counter);
+    }
+
+    /// the block a conditional tile currently behaves like, other blocks are returned as they are
+    pub fn resolve_block<'a>(&'a self, block: &'a MapBlockTypes) -> &'a MapBlockTypes {
+        if let MapBlockTypes::Conditional(tile_id) = block {
+            match self
+                .conditional_tiles
+                .iter()
+                .find(|tile| tile.id == *tile_id)
+            {
+                Some(tile) if tile.condition.is_met(self) => &tile.then,
+                Some(tile) => &tile.otherwise,
+                None => &MapBlockTypes::NotWalkable,
+            }
+        } else {
+            block
+        }
+    }
+
+    /// the current map with all conditional tiles resolved, used to draw the map
+    pub fn visible_map(&self) -> crate::map_gen::Map {
+        self.maps[self.cur_map]
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|block| self.resolve_block(block).clone())
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::flags::Flags;
+    use crate::game::{Game, MapBlockTypes, Movement};
+
+    #[test]
+    fn test_flags_and_counters() {
+        let mut flags = Flags::default();
+        assert!(!flags.is_set("lever"));
+
+        flags.set("lever");
+        flags.add("kills", 2);
+        flags.add("kills", 1);
+
+        assert!(flags.is_set("lever"));
+        assert_eq!(flags.get("kills"), 3);
+        flags.unset("lever");
+        assert!(!flags.is_set("lever"));
+    }
+
+    #[test]
+    fn test_conditional_tile() {
+        let mut game = Game::from_yaml(
+            "
+maps:
+    - '|_|?0|'
+conditional_tiles:
+    - id: 0
+      condition:
+          counter: kills
+          at_least: 2
+      then: _
+      else: x
+",
+        );
+
+        game.east();
+        assert_eq!(game.pos.i, 0);
+        assert_eq!(game.visible_map()[0][1], MapBlockTypes::NotWalkable);
+
+        game.add_to_counter("kills".to_string(), 2);
+        game.east();
+        assert_eq!(game.pos.i, 1);
+    }
+
+    #[test]
+    fn test_conditional_tile_to_anchor() {
+        let game = Game::from_yaml(
+            "
+maps:
+    - '|_|?0|'
+    - name: crypt
+      tiles: '|_|A(door)|'
+conditional_tiles:
+    - id: 0
+      condition:
+          flag: open
+      then: T(crypt.door)
+      else: x
+",
+        );
+
+        assert_eq!(
+            game.conditional_tiles[0].then,
+            MapBlockTypes::TeleportTrigger(1, 0, 1)
+        );
+    }
+
+    #[test]
+    fn test_unknown_conditional_tile() {
+        let error = crate::custom_layer::parse_scenario(
+            "
+maps: ['|_|']
+global_items: []
+enemies: []
+start_pos: [0, 0]
+player: {name: Hans, character_type: Warrior, total_health: 30, rp_properties: {at: 1, aw: 6, rs: 2}}
+conditional_tiles:
+    - {id: 0, condition: {flag: open}, then: Q, else: x}
+",
+            "",
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("Q"));
+    }
+}
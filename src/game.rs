@@ -1,6 +1,6 @@
 use crate::info_manager::*;
 use crate::map_gen;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug)]
 pub struct Game {
@@ -11,26 +11,113 @@ pub struct Game {
     pub global_items: Vec<ItemProps>,
     pub entities: Vec<Entity>,
     pub maps: Vec<map_gen::Map>,
+    /// maps behind the sides of each map
+    pub edges: Vec<crate::edges::Edges>,
+    pub dungeon: Option<crate::depth::Dungeon>,
+    /// maps of the levels of the dungeon by depth, starting at depth 1, generated ones are added
+    pub levels: Vec<usize>,
+    /// where the player went down the stairs, one for each level of depth
+    pub descents: Vec<crate::depth::Descent>,
     pub cur_map: usize,
     pub pos: Pos,
     pub inventory: Inventory,
+    pub gold: usize,
+    pub merchants: Vec<crate::merchant::Merchant>,
+    pub trade: Option<crate::merchant::Trade>,
+    pub doors: Vec<crate::door::DoorProps>,
+    pub levers: Vec<crate::door::LeverProps>,
+    pub max_hp: i32,
+    pub rp_properties: crate::custom_layer::RpProperties,
+    pub status_effects: Vec<crate::status::StatusEffect>,
+    pub traps: Vec<crate::trap::TrapProps>,
+    pub chests: Vec<crate::chest::ChestProps>,
+    pub shrines: Vec<crate::shrine::ShrineProps>,
+    pub shrine_states: Vec<crate::shrine::ShrineState>,
+    /// steps walked by the player
+    pub turn: usize,
+    pub loot: Option<crate::chest::Loot>,
+    pub npcs: Vec<crate::dialogue::Npc>,
+    pub dialogue: Option<crate::dialogue::Dialogue>,
+    pub quests: Vec<crate::quest::Quest>,
+    pub quest_log: Vec<crate::quest::QuestState>,
+    pub flags: crate::flags::Flags,
+    pub conditional_tiles: Vec<crate::flags::ConditionalTile>,
+    pub events: Vec<crate::events::Event>,
+    /// indices in events which fired already and only fire once
+    pub fired_events: Vec<usize>,
+    pub event_depth: usize,
+    pub scripts: Vec<crate::script::Script>,
+    pub readables: Vec<crate::lore::Readable>,
+    /// ids of readables the player has read
+    pub read_readables: Vec<usize>,
+    pub reader: Option<crate::lore::Reader>,
+    pub slot_menu: Option<crate::save::SlotMenu>,
+    /// whether map transitions and fights write autosaves
+    pub autosave: bool,
+    /// path of the scenario file the game was started with
+    pub scenario_path: String,
+    /// content hash of the scenario file the game was started with
+    pub scenario_hash: u64,
     pub fight: Option<crate::fight::Fight>,
+    /// source of all dice rolls, seeded to replay a game
+    pub rng: rand::rngs::StdRng,
+    /// seed given on the command line, new games are seeded with it again
+    pub seed: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
+/// scenario played when no other one is given on the command line
+pub const DEFAULT_SCENARIO: &str = "test.yaml";
+
+/// scenario of Game::from_yaml, two open 6x6 maps
+#[cfg(test)]
+const TEST_SCENARIO: &str = "
+maps:
+    - >-
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+    - >-
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+        |_|_|_|_|_|_|
+start_pos: [0, 0]
+global_items:
+    - {id: 0, name: Sword, at: 6, price: 12}
+    - {id: 1, name: Boots, at: 3, price: 6}
+    - {id: 2, name: Bow, at: 3, price: 9}
+    - {id: 3, name: Rusty Key, at: 0}
+enemies:
+    - {id: 0, name: Troll, hp: 10, at: 3, aw: 3, type_: Enemy, meele_weapon: 0}
+player:
+    name: Hans
+    character_type: Warrior
+    total_health: 30
+    rp_properties: {at: 1, aw: 6, rs: 2}
+";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Pos {
     pub i: usize,
     pub j: usize,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ItemProps {
     pub id: usize,
     pub name: String,
     pub at: i32,
+    /// gold a merchant asks for the item, selling returns half of it
+    #[serde(default)]
+    pub price: usize,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Entity {
     pub id: isize,
     pub name: String,
@@ -44,7 +131,7 @@ pub struct Entity {
     pub aw: Option<usize>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum EntityType {
     Player,
     Enemy,
@@ -65,6 +152,14 @@ fn block_is_new_map(map_block: &MapBlockTypes) -> Option<usize> {
     }
 }
 
+fn block_is_merchant(map_block: &MapBlockTypes) -> Option<usize> {
+    if let &MapBlockTypes::MerchantTrigger(merchant_id) = map_block {
+        Some(merchant_id)
+    } else {
+        None
+    }
+}
+
 fn block_is_teleport_trigger(map_block: &MapBlockTypes) -> Option<(usize, usize, usize)> {
     if let &MapBlockTypes::TeleportTrigger(teleport_id, j, i) = map_block {
         Some((teleport_id, j, i))
@@ -77,50 +172,122 @@ impl Movement for Game {
     fn movement(&mut self, incoming_block: Pos, new_map_pos: Pos, is_edge: bool) {
         let new_map_block_id = block_is_new_map(self.get_map_block_type(&self.pos.clone()));
         if is_edge && new_map_block_id.is_some() {
-            self.cur_map = new_map_block_id.unwrap();
-            self.pos = new_map_pos;
+            self.enter_map(new_map_block_id.unwrap(), new_map_pos);
             return;
         }
 
         let teleport_id = block_is_teleport_trigger(self.get_map_block_type(&incoming_block));
         if teleport_id.is_some() {
             let (teleport_id, j, i) = teleport_id.unwrap();
-            self.cur_map = teleport_id;
             let newpos = Pos { i, j };
-            self.pos = newpos;
+            self.enter_map(teleport_id, newpos);
+            return;
+        }
+
+        if let Some(merchant_id) = block_is_merchant(self.get_map_block_type(&incoming_block)) {
+            // merchants block the way, bumping into them opens the trade screen
+            self.open_trade(merchant_id);
             return;
         }
+
+        match self.get_map_block_type(&incoming_block).clone() {
+            MapBlockTypes::Door(door_id, false) => {
+                self.try_open_door(&incoming_block, door_id);
+                return;
+            }
+            MapBlockTypes::Chest(chest_id) => {
+                self.open_chest(chest_id);
+                return;
+            }
+            MapBlockTypes::Npc(npc_id) => {
+                self.start_dialogue(npc_id);
+                return;
+            }
+            MapBlockTypes::Readable(readable_id) => {
+                self.open_reader(readable_id);
+                return;
+            }
+            MapBlockTypes::Shrine(shrine_id) => {
+                self.use_shrine(&incoming_block, shrine_id);
+                return;
+            }
+            MapBlockTypes::Lever(lever_id, pulled) => {
+                if !pulled {
+                    self.pull_lever(&incoming_block, lever_id);
+                }
+                return;
+            }
+            _ => {}
+        }
+
         if !is_edge
             && self.get_map_block_type(&incoming_block.clone()) != &MapBlockTypes::NotWalkable
         {
+            let (map, pos) = (self.cur_map, self.pos.clone());
             if let &MapBlockTypes::ItemTrigger(item_id) =
                 self.get_map_block_type(&incoming_block.clone())
             {
-                self.inventory
-                    .add_item(item_id, &self.global_items)
-                    .unwrap();
-
+                match self.pick_up_item(item_id) {
+                    Ok(()) => {
+                        self.info_queue.queue(
+                            "Item".to_string(),
+                            "You have collected a ".to_string()
+                                + &self.global_items[item_id].name.clone(),
+                        );
+
+                        self.remove_item_from_map(&incoming_block);
+                    }
+                    Err(e) => log::warn!("{} (item {} on map {})", e, item_id, self.cur_map),
+                }
+            } else if let &MapBlockTypes::GoldTrigger(amount) =
+                self.get_map_block_type(&incoming_block.clone())
+            {
+                self.gold += amount;
                 self.info_queue.queue(
-                    "Item".to_string(),
-                    "You have collected a ".to_string() + &self.global_items[item_id].name.clone(),
+                    "Gold".to_string(),
+                    format!("You have found {} gold", amount),
                 );
 
                 self.remove_item_from_map(&incoming_block);
+            } else if let MapBlockTypes::FlagTrigger(flag) =
+                self.get_map_block_type(&incoming_block.clone())
+            {
+                self.set_flag(flag.clone());
             } else if let &MapBlockTypes::EnemyTrigger(enemy_id) =
                 self.get_map_block_type(&incoming_block.clone())
             {
-                self.active_menu_item = crate::MenuItem::Fight;
-                self.info_queue.queue(
-                    "Enemy".to_string(),
-                    "You have encountered a ".to_string()
-                        + &self.entities[enemy_id + 1].name.clone(),
-                );
+                self.start_encounter(enemy_id);
+            }
+            // events and quest rewards of the pickup may have moved the player already
+            if self.cur_map != map || self.pos != pos {
+                return;
             }
             self.pos = incoming_block;
+            self.turn += 1;
+
+            if let MapBlockTypes::Trap(trap_id, visible) =
+                self.get_map_block_type(&self.pos).clone()
+            {
+                self.step_on_trap(trap_id, visible);
+            }
+            self.tick_status_effects();
+            if self.is_defeated() {
+                return;
+            }
+            self.search_for_traps();
+            self.fire_events(crate::events::Trigger::Turn(self.turn));
+            self.fire_events(crate::events::Trigger::Enter(crate::events::Area {
+                map: self.cur_map,
+                from: [self.pos.j, self.pos.i],
+                to: None,
+            }));
         }
     }
 
     fn north(&mut self) {
+        if self.cross_edge(crate::edges::Side::North) {
+            return;
+        }
         let mut incoming_block = Pos {
             j: self.pos.j,
             i: self.pos.i,
@@ -139,6 +306,9 @@ impl Movement for Game {
     }
 
     fn south(&mut self) {
+        if self.cross_edge(crate::edges::Side::South) {
+            return;
+        }
         let mut incoming_block = Pos {
             j: self.pos.j,
             i: self.pos.i,
@@ -157,6 +327,9 @@ impl Movement for Game {
     }
 
     fn west(&mut self) {
+        if self.cross_edge(crate::edges::Side::West) {
+            return;
+        }
         let mut incoming_block = Pos {
             j: self.pos.j,
             i: self.pos.i,
@@ -175,6 +348,9 @@ impl Movement for Game {
     }
 
     fn east(&mut self) {
+        if self.cross_edge(crate::edges::Side::East) {
+            return;
+        }
         let mut incoming_block = Pos {
             j: self.pos.j,
             i: self.pos.i,
@@ -194,26 +370,109 @@ impl Movement for Game {
 }
 
 impl Game {
+    /// whether the map has a tile at j and i
+    /// replays the dice rolls of `seed`, without one they stay random
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+        if let Some(seed) = seed {
+            self.rng = rand::SeedableRng::seed_from_u64(seed);
+        }
+    }
+
+    pub fn has_tile(&self, map: usize, j: usize, i: usize) -> bool {
+        self.maps
+            .get(map)
+            .and_then(|map| map.get(j))
+            .is_some_and(|row| i < row.len())
+    }
+
     fn get_map_block_type(&self, pos: &Pos) -> &MapBlockTypes {
         let row = &self.maps[self.cur_map][pos.j];
         let map_block = &row[pos.i];
-        return &map_block;
+        return self.resolve_block(map_block);
     }
 
+    /// the game of the default scenario, which is known to load
+    #[cfg(test)]
     pub fn new() -> Game {
-        let mut game_settings = match crate::custom_layer::parse_game_settings("test.yaml") {
-            Ok(v) => v,
-            Err(e) => {
-                println!("Error while parsing toml file: {:?}", e);
-                std::process::exit(0);
-            }
-        };
+        Game::with_scenario(DEFAULT_SCENARIO).expect("the default scenario to load")
+    }
 
-        let maps: Vec<map_gen::Map> = game_settings
-            .maps
+    pub fn with_scenario(scenario_path: &str) -> Result<Game, crate::custom_layer::ScenarioError> {
+        let game_settings = crate::custom_layer::parse_game_settings(scenario_path)?;
+        Game::with_settings(game_settings, scenario_path)
+    }
+
+    /// a game of a small scenario, the top level sections in `yaml` replace the ones of it
+    #[cfg(test)]
+    pub fn from_yaml(yaml: &str) -> Game {
+        let mut scenario: serde_yaml::Mapping = serde_yaml::from_str(TEST_SCENARIO).unwrap();
+        let sections: Option<serde_yaml::Mapping> = serde_yaml::from_str(yaml).unwrap();
+        scenario.extend(sections.unwrap_or_default());
+        let contents = serde_yaml::to_string(&scenario).unwrap();
+        let game_settings = crate::custom_layer::parse_scenario(&contents, "").unwrap();
+        Game::with_settings(game_settings, "").expect("the test scenario to load")
+    }
+
+    pub fn with_settings(
+        mut game_settings: crate::custom_layer::GameSettings,
+        scenario_path: &str,
+    ) -> Result<Game, crate::custom_layer::ScenarioError> {
+        let (maps, names) =
+            map_gen::generate_named_maps(&game_settings.maps, &game_settings.legend).map_err(
+                |source| crate::custom_layer::ScenarioError::Map {
+                    path: scenario_path.to_string(),
+                    source,
+                },
+            )?;
+        let conditional_tiles = game_settings
+            .conditional_tiles
             .iter()
-            .map(|map_str| map_gen::generate_map(map_str.to_string()))
-            .collect();
+            .map(|tile| {
+                tile.resolve(&names).map_err(|source| {
+                    crate::custom_layer::ScenarioError::Conditional {
+                        path: scenario_path.to_string(),
+                        id: tile.id,
+                        source,
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let edges = crate::edges::resolve_edges(&game_settings.maps, &maps).map_err(|source| {
+            crate::custom_layer::ScenarioError::Edge {
+                path: scenario_path.to_string(),
+                source,
+            }
+        })?;
+        let levels = crate::depth::resolve_levels(
+            game_settings.dungeon.as_ref(),
+            &game_settings.maps,
+            &maps,
+        )
+        .map_err(|source| crate::custom_layer::ScenarioError::Depth {
+            path: scenario_path.to_string(),
+            source,
+        })?;
+        let [start_i, start_j] = game_settings.start_pos;
+        match maps
+            .first()
+            .and_then(|map| map.get(start_j))
+            .and_then(|row| row.get(start_i))
+        {
+            None => {
+                return Err(crate::custom_layer::ScenarioError::StartOutside {
+                    path: scenario_path.to_string(),
+                    start_pos: game_settings.start_pos,
+                })
+            }
+            Some(MapBlockTypes::NotWalkable) => {
+                return Err(crate::custom_layer::ScenarioError::StartInWall {
+                    path: scenario_path.to_string(),
+                    start_pos: game_settings.start_pos,
+                })
+            }
+            Some(_) => {}
+        }
 
         let mut entities = vec![Entity {
             id: 0,
@@ -227,22 +486,164 @@ impl Game {
         }];
         entities.append(&mut game_settings.enemies);
 
-        return Game {
+        let mut game = Game {
             playername: game_settings.player.name,
             cur_map: 0,
             // health: game_settings.player.total_health,
             global_items: game_settings.global_items,
             entities,
             inventory: Inventory::new(),
+            gold: game_settings.player.gold,
+            merchants: game_settings.merchants,
+            trade: None,
+            doors: game_settings.doors,
+            levers: game_settings.levers,
+            max_hp: game_settings.player.total_health,
+            rp_properties: game_settings.player.rp_properties,
+            status_effects: vec![],
+            traps: game_settings.traps,
+            chests: game_settings.chests,
+            shrines: game_settings.shrines,
+            shrine_states: vec![],
+            turn: 0,
+            loot: None,
+            npcs: game_settings.npcs,
+            dialogue: None,
+            quests: game_settings.quests,
+            quest_log: vec![],
+            flags: crate::flags::Flags::default(),
+            conditional_tiles,
+            events: game_settings.events,
+            fired_events: vec![],
+            event_depth: 0,
+            scripts: game_settings.scripts,
+            readables: game_settings.readables,
+            read_readables: vec![],
+            reader: None,
+            slot_menu: None,
+            autosave: false,
+            scenario_path: scenario_path.to_string(),
+            scenario_hash: game_settings.content_hash,
             pos: Pos {
                 i: game_settings.start_pos[0],
                 j: game_settings.start_pos[1],
             },
             info_queue: InfoQueue::new(),
             maps,
+            edges,
+            dungeon: game_settings.dungeon,
+            levels,
+            descents: vec![],
             active_menu_item: crate::MenuItem::Game,
             fight: None,
+            rng: rand::SeedableRng::from_entropy(),
+            seed: None,
+        };
+        game.load_scripts();
+        game.reveal_visible_traps();
+        game.start_visible_quests();
+        return Ok(game);
+    }
+
+    /// moves the player to `pos` on another map
+    pub fn enter_map(&mut self, map: usize, pos: Pos) {
+        self.cur_map = map;
+        self.pos = pos;
+        self.autosave();
+        self.quest_event(crate::quest::QuestEvent::MapReached(map));
+        self.fire_events(crate::events::Trigger::Enter(crate::events::Area {
+            map,
+            from: [self.pos.j, self.pos.i],
+            to: None,
+        }));
+    }
+
+    /// adds an item to the inventory, every item the player receives goes through here
+    pub fn pick_up_item(&mut self, item_id: usize) -> Result<(), String> {
+        self.inventory.add_item(item_id, &self.global_items)?;
+        self.quest_event(crate::quest::QuestEvent::ItemCollected(item_id));
+        self.fire_events(crate::events::Trigger::Pickup(item_id));
+        Ok(())
+    }
+
+    pub fn start_encounter(&mut self, enemy_id: usize) {
+        self.autosave();
+        self.active_menu_item = crate::MenuItem::Fight;
+        self.info_queue.queue(
+            "Enemy".to_string(),
+            "You have encountered a ".to_string() + &self.entities[enemy_id + 1].name.clone(),
+        );
+
+        let mut player = self.entities[0].clone();
+        player.at = Some(self.rp_properties.at.max(0) as usize);
+        player.aw = Some(self.rp_properties.aw.max(0) as usize);
+        let mut fight = crate::fight::Fight::new(vec![player, self.entities[enemy_id + 1].clone()]);
+        fight.ini(&mut self.rng);
+        self.fight = Some(fight);
+    }
+
+    pub fn fight_round(&mut self) {
+        let mut fight = match self.fight.take() {
+            Some(v) => v,
+            None => return,
         };
+        for message in fight.round(&mut self.rng) {
+            self.info_queue.queue("Fight".to_string(), message);
+        }
+        self.entities[0].hp = fight.entities[0].hp;
+
+        if !fight.is_over() {
+            self.fight = Some(fight);
+        } else if self.is_defeated() {
+            self.player_defeated();
+        } else {
+            self.enemy_defeated(fight.entities[1].id as usize);
+        }
+    }
+
+    pub fn is_defeated(&self) -> bool {
+        self.entities[0].hp <= 0
+    }
+
+    /// takes hp from the player outside of fights, the game ends once none are left
+    pub fn damage_player(&mut self, damage: i32) {
+        if self.is_defeated() {
+            return;
+        }
+        self.entities[0].hp = self.entities[0].hp.saturating_sub(damage);
+        if self.is_defeated() {
+            self.player_defeated();
+        }
+    }
+
+    /// the game is over, the player can only load a save or start a new game
+    pub fn player_defeated(&mut self) {
+        self.fight = None;
+        self.info_queue
+            .queue("Fight".to_string(), "You have been defeated".to_string());
+        self.open_slot_menu(false);
+    }
+
+    pub fn flee(&mut self) {
+        self.fight = None;
+        self.active_menu_item = crate::MenuItem::Game;
+        self.info_queue
+            .queue("Fight".to_string(), "You have fled".to_string());
+    }
+
+    pub fn enemy_defeated(&mut self, enemy_id: usize) {
+        self.fight = None;
+        self.active_menu_item = crate::MenuItem::Game;
+        self.info_queue.queue(
+            "Fight".to_string(),
+            "You have defeated the ".to_string() + &self.entities[enemy_id + 1].name,
+        );
+        if let MapBlockTypes::EnemyTrigger(_) = self.get_map_block_type(&self.pos) {
+            self.remove_item_from_map(&self.pos.clone());
+        }
+        self.add_to_counter(format!("enemy_{}_defeated", enemy_id), 1);
+        self.quest_event(crate::quest::QuestEvent::EnemyDefeated(enemy_id));
+        self.fire_events(crate::events::Trigger::Defeat(enemy_id));
     }
 
     pub fn remove_item_from_map(&mut self, pos: &Pos) {
@@ -256,18 +657,22 @@ impl Game {
         }
     }
 
+    pub fn set_map_block(&mut self, pos: &Pos, block: MapBlockTypes) {
+        self.maps[self.cur_map][pos.j][pos.i] = block;
+    }
+
     pub fn add_item_to_map(&mut self, pos: &Pos, item_id: usize) {
         todo!("impl add item to map");
     }
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InventoryElement {
     pub acquisition_time: u128,
     pub props: ItemProps,
 }
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Inventory {
     pub inventory: Vec<InventoryElement>,
 }
@@ -277,7 +682,11 @@ impl Inventory {
         return Inventory { inventory: vec![] };
     }
 
-    fn get_item_props(&self, item_id: &usize, global_items: &Vec<ItemProps>) -> Option<ItemProps> {
+    pub fn get_item_props(
+        &self,
+        item_id: &usize,
+        global_items: &Vec<ItemProps>,
+    ) -> Option<ItemProps> {
         for item_prop in global_items {
             if item_prop.id == *item_id {
                 return Some(item_prop.clone());
@@ -287,7 +696,7 @@ impl Inventory {
         return None;
     }
 
-    fn add_item(&mut self, id: usize, global_items: &Vec<ItemProps>) -> Result<(), String> {
+    pub fn add_item(&mut self, id: usize, global_items: &Vec<ItemProps>) -> Result<(), String> {
         let props = self
             .get_item_props(&id, global_items)
             .ok_or("Item not found".to_string())?;
@@ -303,6 +712,21 @@ impl Inventory {
         return Ok(());
     }
 
+    /// index of the first inventory element with the given item_id
+    pub fn position_of(&self, item_id: usize) -> Option<usize> {
+        self.inventory
+            .iter()
+            .position(|item| item.props.id == item_id)
+    }
+
+    pub fn remove_item(&mut self, index: usize) -> Option<InventoryElement> {
+        if index < self.inventory.len() {
+            Some(self.inventory.remove(index))
+        } else {
+            None
+        }
+    }
+
     pub fn to_string(&self) -> String {
         let mut str = "".to_string();
         for item in &self.inventory {
@@ -321,7 +745,7 @@ pub trait Movement {
     fn movement(&mut self, new_pos: Pos, new_map_pos: Pos, is_edge: bool) {}
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub enum MapBlockTypes {
     Path,
     NotWalkable,
@@ -329,4 +753,30 @@ pub enum MapBlockTypes {
     TeleportTrigger(usize, usize, usize),
     EnemyTrigger(usize),
     ItemTrigger(usize),
+    /// amount of gold picked up when stepping on the block
+    GoldTrigger(usize),
+    /// merchant_id in merchants
+    MerchantTrigger(usize),
+    /// door_id in doors and whether the door is open
+    Door(usize, bool),
+    /// lever_id in levers and whether the lever was pulled
+    Lever(usize, bool),
+    /// chest_id in chests
+    Chest(usize),
+    /// trap_id in traps and whether the trap is visible
+    Trap(usize, bool),
+    /// shrine_id in shrines
+    Shrine(usize),
+    /// npc_id in npcs
+    Npc(usize),
+    /// conditional_tile_id in conditional_tiles
+    Conditional(usize),
+    /// flag set when stepping on the block
+    FlagTrigger(String),
+    /// readable_id in readables
+    Readable(usize),
+    /// leads one level deeper into the dungeon
+    StairsDown,
+    /// leads back to where the player came down
+    StairsUp,
 }
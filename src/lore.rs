@@ -0,0 +1,168 @@
+use crate::game::Game;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadableKind {
+    #[default]
+    Sign,
+    Book,
+    Note,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Readable {
+    pub id: usize,
+    #[serde(default)]
+    pub kind: ReadableKind,
+    pub title: String,
+    pub text: String,
+    /// entry added to the journal the first time the text is read
+    pub journal: Option<String>,
+}
+
+/// state of the currently open reader pane
+#[derive(Debug, Clone)]
+pub struct Reader {
+    /// index in Game::readables
+    pub readable: usize,
+    /// number of lines scrolled down
+    pub scroll: u16,
+}
+
+impl ReadableKind {
+    pub fn name(&self) -> &str {
+        match self {
+            ReadableKind::Sign => "Sign",
+            ReadableKind::Book => "Book",
+            ReadableKind::Note => "Note",
+        }
+    }
+}
+
+impl Game {
+    pub fn open_reader(&mut self, readable_id: usize) {
+        let readable = match self.readables.iter().position(|r| r.id == readable_id) {
+            Some(v) => v,
+            None => {
+                log::warn!("readable {} not found", readable_id);
+                return;
+            }
+        };
+        if !self.read_readables.contains(&readable_id) {
+            self.read_readables.push(readable_id);
+            // lets events and dialogues check whether the text was read
+            self.set_flag(format!("readable_{}_read", readable_id));
+            if self.readables[readable].journal.is_some() {
+                self.info_queue.queue(
+                    "Journal".to_string(),
+                    format!("New journal entry: {}", self.readables[readable].title),
+                );
+            }
+        }
+        self.reader = Some(Reader {
+            readable,
+            scroll: 0,
+        });
+        self.active_menu_item = crate::MenuItem::Reader;
+    }
+
+    pub fn close_reader(&mut self) {
+        self.reader = None;
+        self.active_menu_item = crate::MenuItem::Game;
+    }
+
+    pub fn reader_up(&mut self) {
+        if let Some(reader) = self.reader.as_mut() {
+            reader.scroll = reader.scroll.saturating_sub(1);
+        }
+    }
+
+    pub fn reader_down(&mut self) {
+        if let Some(reader) = self.reader.as_mut() {
+            let lines = self.readables[reader.readable].text.lines().count() as u16;
+            if reader.scroll + 1 < lines {
+                reader.scroll += 1;
+            }
+        }
+    }
+
+    /// journal entries of all read texts in the order they were read
+    pub fn journal_entries(&self) -> Vec<(&str, &str)> {
+        self.read_readables
+            .iter()
+            .filter_map(|id| self.readables.iter().find(|r| r.id == *id))
+            .filter_map(|r| Some((r.title.as_str(), r.journal.as_deref()?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, Movement};
+    use crate::MenuItem;
+
+    fn lore_game() -> Game {
+        Game::from_yaml(
+            "
+maps:
+    - '|_|_|_|_| |_|_|_|R0| |_|_|_|_|'
+start_pos: [3, 2]
+readables:
+    - id: 0
+      title: Warning
+      text: Beware of the troll
+    - id: 1
+      kind: book
+      title: Diary
+      text: |
+          Day one
+          Day two
+          Day three
+      journal: The diary mentions a troll.
+",
+        )
+    }
+
+    #[test]
+    fn test_read_sign() {
+        let mut game = lore_game();
+
+        game.north();
+
+        assert_eq!(game.active_menu_item, MenuItem::Reader);
+        assert_eq!(game.pos.j, 2);
+        assert!(game.flags.is_set("readable_0_read"));
+        assert!(game.journal_entries().is_empty());
+    }
+
+    #[test]
+    fn test_journal_entry() {
+        let mut game = lore_game();
+
+        game.open_reader(1);
+        game.close_reader();
+        game.open_reader(1);
+
+        assert_eq!(game.read_readables, vec![1]);
+        assert_eq!(
+            game.journal_entries(),
+            vec![("Diary", "The diary mentions a troll.")]
+        );
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut game = lore_game();
+        game.open_reader(1);
+
+        game.reader_up();
+        for _ in 0..5 {
+            game.reader_down();
+        }
+
+        assert_eq!(game.reader.as_ref().unwrap().scroll, 2);
+        game.close_reader();
+        assert_eq!(game.active_menu_item, MenuItem::Game);
+    }
+}
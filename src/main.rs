@@ -2,7 +2,6 @@ use crossterm::{
     event::{self, Event as CEvent, KeyCode},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
-use log::LevelFilter;
 use log4rs::append::file::FileAppender;
 use log4rs::config::{Appender, Config, Root};
 use log4rs::encode::pattern::PatternEncoder;
@@ -15,15 +14,36 @@ use tui::{
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Style},
     text::{Span, Spans},
-    widgets::{Block, BorderType, Borders, Paragraph},
+    widgets::{Block, BorderType, Borders, Paragraph, Tabs},
     Terminal,
 };
 
+mod chest;
+mod cli;
 mod custom_layer;
+mod depth;
+mod dialogue;
+mod door;
+mod edges;
+mod editor;
+mod events;
 mod fight;
+mod flags;
 mod game;
 mod info_manager;
+mod lore;
 mod map_gen;
+mod merchant;
+mod procgen;
+mod quest;
+mod reachability;
+mod save;
+mod script;
+mod shrine;
+mod status;
+mod tiled;
+mod trap;
+mod validate;
 use game::*;
 
 enum Event<I> {
@@ -37,24 +57,69 @@ pub enum MenuItem {
     Help,
     Inventory,
     Fight,
+    Merchant,
+    Loot,
+    Dialogue,
+    Journal,
+    Reader,
+    Saves,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = match cli::parse_args(std::env::args().skip(1)) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let success = match options.command {
+        cli::Command::Help => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        cli::Command::Validate => cli::validate(&options),
+        cli::Command::RenderMap(map) => cli::render_map(&options, map),
+        cli::Command::Edit => return edit(&options),
+        cli::Command::Play => return play(&options),
+    };
+    if !success {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn play(options: &cli::Options) -> Result<(), Box<dyn std::error::Error>> {
     let logfile = FileAppender::builder()
         .encoder(Box::new(PatternEncoder::new("{l} - {m}\n")))
-        .build("output.log")?;
+        .build(&options.log_file)?;
 
     let config = Config::builder()
         .appender(Appender::builder().build("logfile", Box::new(logfile)))
-        .build(Root::builder().appender("logfile").build(LevelFilter::Info))?;
+        .build(Root::builder().appender("logfile").build(options.log_level))?;
 
     log4rs::init_config(config)?;
 
+    let mut global_game = match Game::with_scenario(&options.scenario) {
+        Ok(v) => v,
+        Err(e) => {
+            log::error!("can not start the game: {}", e);
+            show_startup_error(&options.scenario, &e)?;
+            std::process::exit(1);
+        }
+    };
+
     enable_raw_mode().expect("can run in raw mode");
 
     let (tx, rx) = mpsc::channel();
     let tick_rate = Duration::from_millis(100);
-    let mut global_game = Game::new();
+    global_game.autosave = true;
+    global_game.set_seed(options.seed);
+    if let Some(slot) = options.slot {
+        global_game.load_save(&save::slot_path(slot));
+    } else if save::has_saves() {
+        global_game.open_slot_menu(false);
+    }
     thread::spawn(move || {
         let mut last_tick = Instant::now();
         loop {
@@ -136,40 +201,32 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     rect.render_widget(render_inventory(&global_game), chunks[0])
                 }
                 MenuItem::Fight => rect.render_widget(render_fight(&mut global_game), chunks[0]),
+                MenuItem::Merchant => rect.render_widget(render_merchant(&global_game), chunks[0]),
+                MenuItem::Loot => rect.render_widget(render_loot(&global_game), chunks[0]),
+                MenuItem::Dialogue => rect.render_widget(render_dialogue(&global_game), chunks[0]),
+                MenuItem::Journal => rect.render_widget(render_journal(&global_game), chunks[0]),
+                MenuItem::Reader => rect.render_widget(render_reader(&global_game), chunks[0]),
+                MenuItem::Saves => rect.render_widget(render_saves(&global_game), chunks[0]),
             }
         })?;
 
         match rx.recv()? {
-            Event::Input(event) => match event.code {
-                KeyCode::Char('q') => {
+            Event::Input(event) => {
+                if event.code == KeyCode::Char('q') {
                     disable_raw_mode()?;
                     terminal.show_cursor()?;
                     break;
                 }
-                KeyCode::Char('w') => global_game.north(),
-                KeyCode::Up => global_game.north(),
-                KeyCode::Char('a') => global_game.west(),
-                KeyCode::Left => global_game.west(),
-                KeyCode::Char('s') => global_game.south(),
-                KeyCode::Down => global_game.south(),
-                KeyCode::Char('d') => global_game.east(),
-                KeyCode::Right => global_game.east(),
-                KeyCode::Char('h') => {
-                    if global_game.active_menu_item == MenuItem::Game {
-                        global_game.active_menu_item = MenuItem::Help;
-                    } else {
-                        global_game.active_menu_item = MenuItem::Game;
-                    }
-                }
-                KeyCode::Char('i') => {
-                    if global_game.active_menu_item == MenuItem::Game {
-                        global_game.active_menu_item = MenuItem::Inventory;
-                    } else {
-                        global_game.active_menu_item = MenuItem::Game;
-                    }
+                match global_game.active_menu_item {
+                    MenuItem::Merchant => handle_merchant_input(&mut global_game, event.code),
+                    MenuItem::Loot => handle_loot_input(&mut global_game, event.code),
+                    MenuItem::Dialogue => handle_dialogue_input(&mut global_game, event.code),
+                    MenuItem::Fight => handle_fight_input(&mut global_game, event.code),
+                    MenuItem::Reader => handle_reader_input(&mut global_game, event.code),
+                    MenuItem::Saves => handle_saves_input(&mut global_game, event.code),
+                    _ => handle_game_input(&mut global_game, event.code),
                 }
-                _ => {}
-            },
+            }
             Event::Tick => {}
         }
     }
@@ -177,32 +234,419 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// paints the maps of the scenario until q is pressed
+fn edit(options: &cli::Options) -> Result<(), Box<dyn std::error::Error>> {
+    let mut editor = match editor::Editor::open(&options.scenario) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    enable_raw_mode()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    terminal.clear()?;
+    loop {
+        terminal.draw(|rect| {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(2)
+                .constraints(
+                    [
+                        Constraint::Length(3),
+                        Constraint::Min(2),
+                        Constraint::Length(6),
+                    ]
+                    .as_ref(),
+                )
+                .split(rect.size());
+            let body = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Min(2), Constraint::Length(48)].as_ref())
+                .split(chunks[1]);
+
+            rect.render_widget(render_editor_tabs(&editor), chunks[0]);
+            rect.render_widget(render_editor_map(&editor), body[0]);
+            rect.render_widget(render_editor_problems(&editor), body[1]);
+            rect.render_widget(render_editor_status(&editor), chunks[2]);
+        })?;
+
+        if let CEvent::Key(key) = event::read()? {
+            if !handle_editor_input(&mut editor, key.code) {
+                break;
+            }
+        }
+    }
+    disable_raw_mode()?;
+    terminal.clear()?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+/// returns false once the editor is closed
+fn handle_editor_input(editor: &mut editor::Editor, key: KeyCode) -> bool {
+    if editor.input.is_some() {
+        match key {
+            KeyCode::Char(c) => editor.type_char(c),
+            KeyCode::Backspace => editor.delete_char(),
+            KeyCode::Enter => editor.confirm_input(),
+            KeyCode::Esc => editor.cancel_input(),
+            _ => {}
+        }
+        return true;
+    }
+    editor.message.clear();
+    match key {
+        KeyCode::Up => editor.move_cursor(-1, 0),
+        KeyCode::Down => editor.move_cursor(1, 0),
+        KeyCode::Left => editor.move_cursor(0, -1),
+        KeyCode::Right => editor.move_cursor(0, 1),
+        KeyCode::Char(' ') | KeyCode::Enter => editor.paint(),
+        KeyCode::Char(']') => editor.next_brush(),
+        KeyCode::Char('[') => editor.previous_brush(),
+        KeyCode::Char('+') => editor.number_up(),
+        KeyCode::Char('-') => editor.number_down(),
+        KeyCode::Char('t') => editor.mark_teleport_target(),
+        KeyCode::Char('p') => editor.pick(),
+        KeyCode::Char('e') => editor.start_input(),
+        KeyCode::Char('r') => editor.resize_rows(1),
+        KeyCode::Char('R') => editor.resize_rows(-1),
+        KeyCode::Char('c') => editor.resize_columns(1),
+        KeyCode::Char('C') => editor.resize_columns(-1),
+        KeyCode::Tab => editor.next_map(),
+        KeyCode::BackTab => editor.previous_map(),
+        KeyCode::Char('n') => editor.new_map(),
+        KeyCode::Char('s') => editor.save(),
+        KeyCode::Char('q') => return !editor.quit(),
+        _ => {}
+    }
+    true
+}
+
+fn render_editor_tabs<'a>(editor: &editor::Editor) -> Tabs<'a> {
+    let titles = (0..editor.maps.len())
+        .map(|m| {
+            let changed = if editor.maps[m].changed { "*" } else { "" };
+            Spans::from(vec![Span::raw(format!("map {}{}", m, changed))])
+        })
+        .collect();
+    Tabs::new(titles)
+        .select(editor.cur_map)
+        .highlight_style(Style::default().fg(Color::Black).bg(Color::Yellow))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(if editor.unsaved {
+                    format!("{} (unsaved)", editor.scenario_path)
+                } else {
+                    editor.scenario_path.clone()
+                })
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn render_editor_map<'a>(editor: &editor::Editor) -> Paragraph<'a> {
+    let cursor_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let mut lines = vec![];
+    for (j, row) in editor.maps[editor.cur_map].tiles.iter().enumerate() {
+        let spans: Vec<Span> = row
+            .iter()
+            .enumerate()
+            .map(|(i, tile)| {
+                let symbol = editor::tile_symbol(tile);
+                if j == editor.cursor.j && i == editor.cursor.i {
+                    Span::styled(if symbol == "  " { "<>" } else { symbol }, cursor_style)
+                } else {
+                    Span::raw(symbol)
+                }
+            })
+            .collect();
+        lines.push(Spans::from(spans));
+    }
+
+    Paragraph::new(lines).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(format!(
+                "row {}, tile {}: {}",
+                editor.cursor.j,
+                editor.cursor.i,
+                editor.tile().map_or("", String::as_str)
+            ))
+            .border_type(BorderType::Plain),
+    )
+}
+
+fn render_editor_problems<'a>(editor: &editor::Editor) -> Paragraph<'a> {
+    let mut lines = vec![];
+    for diagnostic in &editor.diagnostics {
+        let color = match diagnostic.severity {
+            validate::Severity::Error => Color::Red,
+            validate::Severity::Warning => Color::Yellow,
+        };
+        lines.push(Spans::from(vec![Span::styled(
+            diagnostic.message.clone(),
+            Style::default().fg(color),
+        )]));
+    }
+    if lines.is_empty() {
+        lines.push(Spans::from(vec![Span::styled(
+            "no problems",
+            Style::default().fg(Color::Green),
+        )]));
+    }
+
+    Paragraph::new(lines)
+        .wrap(tui::widgets::Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(format!(
+                    "{} errors, {} warnings",
+                    editor.errors(),
+                    editor.diagnostics.len() - editor.errors()
+                ))
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn render_editor_status<'a>(editor: &editor::Editor) -> Paragraph<'a> {
+    let first_line = match &editor.input {
+        Some(input) => Spans::from(vec![
+            Span::styled("tile: ", Style::default().fg(Color::Yellow)),
+            Span::raw(input.clone()),
+        ]),
+        None => Spans::from(vec![
+            Span::styled("brush: ", Style::default().fg(Color::Yellow)),
+            Span::raw(editor.brush_name()),
+        ]),
+    };
+    Paragraph::new(vec![
+        first_line,
+        Spans::from(vec![Span::styled(
+            editor.message.clone(),
+            Style::default().fg(Color::LightCyan),
+        )]),
+        Spans::from(vec![Span::raw(
+            "arrows - move    space - paint    [/] - brush    +/- - number    t - teleport target    p - pick    e - type a tile",
+        )]),
+        Spans::from(vec![Span::raw(
+            "r/R - add/remove row    c/C - add/remove column    tab - next map    n - new map    s - save    q - quit",
+        )]),
+    ])
+    .alignment(Alignment::Center)
+    .wrap(tui::widgets::Wrap { trim: true })
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Editor")
+            .border_type(BorderType::Plain),
+    )
+}
+
+fn handle_game_input(global_game: &mut Game, key: KeyCode) {
+    match key {
+        KeyCode::Char('w') => global_game.north(),
+        KeyCode::Up => global_game.north(),
+        KeyCode::Char('a') => global_game.west(),
+        KeyCode::Left => global_game.west(),
+        KeyCode::Char('s') => global_game.south(),
+        KeyCode::Down => global_game.south(),
+        KeyCode::Char('d') => global_game.east(),
+        KeyCode::Right => global_game.east(),
+        KeyCode::Char('h') => {
+            if global_game.active_menu_item == MenuItem::Game {
+                global_game.active_menu_item = MenuItem::Help;
+            } else {
+                global_game.active_menu_item = MenuItem::Game;
+            }
+        }
+        KeyCode::Char('i') => {
+            if global_game.active_menu_item == MenuItem::Game {
+                global_game.active_menu_item = MenuItem::Inventory;
+            } else {
+                global_game.active_menu_item = MenuItem::Game;
+            }
+        }
+        KeyCode::Char('j') => {
+            if global_game.active_menu_item == MenuItem::Game {
+                global_game.active_menu_item = MenuItem::Journal;
+            } else {
+                global_game.active_menu_item = MenuItem::Game;
+            }
+        }
+        KeyCode::Char('v') => global_game.open_slot_menu(true),
+        KeyCode::Char('l') => global_game.open_slot_menu(false),
+        KeyCode::Char('>') => global_game.descend(),
+        KeyCode::Char('<') => global_game.ascend(),
+        _ => {}
+    }
+}
+
+fn handle_fight_input(global_game: &mut Game, key: KeyCode) {
+    match key {
+        KeyCode::Enter => global_game.fight_round(),
+        KeyCode::Esc => global_game.flee(),
+        _ => {}
+    }
+}
+
+fn handle_merchant_input(global_game: &mut Game, key: KeyCode) {
+    match key {
+        KeyCode::Char('w') | KeyCode::Up => global_game.trade_up(),
+        KeyCode::Char('s') | KeyCode::Down => global_game.trade_down(),
+        KeyCode::Char('a') | KeyCode::Left => global_game.trade_switch_side(),
+        KeyCode::Char('d') | KeyCode::Right => global_game.trade_switch_side(),
+        KeyCode::Enter => global_game.trade_confirm(),
+        KeyCode::Esc => global_game.close_trade(),
+        _ => {}
+    }
+}
+
+fn handle_loot_input(global_game: &mut Game, key: KeyCode) {
+    match key {
+        KeyCode::Char('w') | KeyCode::Up => global_game.loot_up(),
+        KeyCode::Char('s') | KeyCode::Down => global_game.loot_down(),
+        KeyCode::Enter => global_game.take_selected_loot(),
+        KeyCode::Char('t') => global_game.take_all_loot(),
+        KeyCode::Esc => global_game.close_loot(),
+        _ => {}
+    }
+}
+
+fn handle_dialogue_input(global_game: &mut Game, key: KeyCode) {
+    match key {
+        KeyCode::Char('w') | KeyCode::Up => global_game.dialogue_up(),
+        KeyCode::Char('s') | KeyCode::Down => global_game.dialogue_down(),
+        KeyCode::Enter => global_game.dialogue_choose(),
+        KeyCode::Esc => global_game.close_dialogue(),
+        _ => {}
+    }
+}
+
+fn handle_reader_input(global_game: &mut Game, key: KeyCode) {
+    match key {
+        KeyCode::Char('w') | KeyCode::Up => global_game.reader_up(),
+        KeyCode::Char('s') | KeyCode::Down => global_game.reader_down(),
+        KeyCode::Enter | KeyCode::Esc => global_game.close_reader(),
+        _ => {}
+    }
+}
+
+fn handle_saves_input(global_game: &mut Game, key: KeyCode) {
+    match key {
+        KeyCode::Char('w') | KeyCode::Up => global_game.slot_menu_up(),
+        KeyCode::Char('s') | KeyCode::Down => global_game.slot_menu_down(),
+        KeyCode::Enter => global_game.slot_menu_confirm(),
+        KeyCode::Esc => global_game.close_slot_menu(),
+        _ => {}
+    }
+}
+
 fn render_home<'a>(global_game: &'a Game) -> Paragraph<'a> {
-    let map_str = map_gen::visulize_map(
-        &global_game.maps[global_game.cur_map],
-        Some(&global_game.pos),
-    );
+    let map_str = map_gen::visulize_map(&global_game.visible_map(), Some(&global_game.pos));
     return get_map_as_paragraph(map_str)
         .alignment(Alignment::Center)
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::White))
-                .title(global_game.playername.as_str())
+                .title(status_line(global_game))
                 .border_type(BorderType::Plain),
         );
 }
 
+fn status_line(global_game: &Game) -> String {
+    let mut status = format!(
+        "{} - {}/{} HP - {} Gold",
+        global_game.playername, global_game.entities[0].hp, global_game.max_hp, global_game.gold
+    );
+    if global_game.dungeon.is_some() || global_game.depth() > 0 {
+        status += &format!(" - Depth {}", global_game.depth());
+    }
+    for effect in &global_game.status_effects {
+        status += &format!(" - {}", effect.name());
+    }
+    status
+}
+
 fn render_inventory<'a>(global_game: &'a Game) -> Paragraph<'a> {
-    return Paragraph::new(global_game.inventory.to_string())
+    return Paragraph::new(format!(
+        "Gold: {}\n\n{}",
+        global_game.gold,
+        global_game.inventory.to_string()
+    ))
+    .alignment(Alignment::Center)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Inventory")
+            .border_type(BorderType::Plain),
+    );
+}
+
+/// shows why the scenario could not be loaded until a key is pressed
+fn show_startup_error(
+    scenario: &str,
+    error: &dyn std::error::Error,
+) -> Result<(), Box<dyn std::error::Error>> {
+    enable_raw_mode()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    terminal.clear()?;
+    terminal.draw(|rect| {
+        let chunks = Layout::default()
+            .margin(2)
+            .constraints([Constraint::Min(2)].as_ref())
+            .split(rect.size());
+        rect.render_widget(render_startup_error(scenario, error), chunks[0]);
+    })?;
+
+    loop {
+        if let CEvent::Key(_) = event::read()? {
+            break;
+        }
+    }
+    disable_raw_mode()?;
+    terminal.clear()?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+fn render_startup_error<'a>(scenario: &str, error: &dyn std::error::Error) -> Paragraph<'a> {
+    let lines = vec![
+        Spans::from(vec![Span::styled(
+            "Can not start the game",
+            Style::default().fg(Color::Red),
+        )]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::raw(error.to_string())]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            format!(
+                "Run `easy-dungeon-scrawl validate {}` to see every problem",
+                scenario
+            ),
+            Style::default().fg(Color::Yellow),
+        )]),
+        Spans::from(vec![Span::raw("Press any key to quit")]),
+    ];
+
+    Paragraph::new(lines)
         .alignment(Alignment::Center)
+        .wrap(tui::widgets::Wrap { trim: true })
         .block(
             Block::default()
                 .borders(Borders::ALL)
                 .style(Style::default().fg(Color::White))
-                .title("Inventory")
+                .title("Error")
                 .border_type(BorderType::Plain),
-        );
+        )
 }
 
 fn render_help<'a>() -> Paragraph<'a> {
@@ -228,7 +672,62 @@ fn render_help<'a>() -> Paragraph<'a> {
         Spans::from(vec![Span::raw("a - move west")]),
         Spans::from(vec![Span::raw("s - move south")]),
         Spans::from(vec![Span::raw("d - move east")]),
+        Spans::from(vec![Span::raw("> - go down the stairs")]),
+        Spans::from(vec![Span::raw("< - go up the stairs")]),
         Spans::from(vec![Span::raw("h - toggle help")]),
+        Spans::from(vec![Span::raw("i - toggle inventory")]),
+        Spans::from(vec![Span::raw("j - toggle quest journal")]),
+        Spans::from(vec![Span::raw("v - save game")]),
+        Spans::from(vec![Span::raw("l - load game")]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            "Fight",
+            Style::default().fg(Color::Yellow),
+        )]),
+        Spans::from(vec![Span::raw("enter - attack")]),
+        Spans::from(vec![Span::raw("esc - flee")]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            "Merchant",
+            Style::default().fg(Color::Yellow),
+        )]),
+        Spans::from(vec![Span::raw("w/s - select item")]),
+        Spans::from(vec![Span::raw("a/d - switch between buy and sell")]),
+        Spans::from(vec![Span::raw("enter - buy/sell selected item")]),
+        Spans::from(vec![Span::raw("esc - leave merchant")]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            "Chest",
+            Style::default().fg(Color::Yellow),
+        )]),
+        Spans::from(vec![Span::raw("w/s - select item")]),
+        Spans::from(vec![Span::raw("enter - take selected item")]),
+        Spans::from(vec![Span::raw("t - take all items")]),
+        Spans::from(vec![Span::raw("esc - close chest")]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            "Dialogue",
+            Style::default().fg(Color::Yellow),
+        )]),
+        Spans::from(vec![Span::raw("w/s - select answer")]),
+        Spans::from(vec![Span::raw("enter - answer")]),
+        Spans::from(vec![Span::raw("esc - leave dialogue")]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            "Reading",
+            Style::default().fg(Color::Yellow),
+        )]),
+        Spans::from(vec![Span::raw("w/s - scroll")]),
+        Spans::from(vec![Span::raw("enter/esc - stop reading")]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            "Save/Load",
+            Style::default().fg(Color::Yellow),
+        )]),
+        Spans::from(vec![Span::raw("w/s - select slot")]),
+        Spans::from(vec![Span::raw("enter - save/load selected slot")]),
+        Spans::from(vec![Span::raw("esc - back to the game")]),
+        Spans::from(vec![Span::raw("")]),
         Spans::from(vec![Span::raw("q - quit")]),
     ])
     .alignment(Alignment::Center)
@@ -248,7 +747,7 @@ fn render_fight(global_game: &mut Game) -> Paragraph {
 
     if fight.is_none() {
         let mut new_fight = crate::fight::Fight::new(global_game.entities.clone());
-        new_fight.ini();
+        new_fight.ini(&mut global_game.rng);
         global_game.fight = Some(new_fight);
     } else {
         for item in &fight.as_ref().unwrap().iniative {
@@ -256,13 +755,18 @@ fn render_fight(global_game: &mut Game) -> Paragraph {
             ini_str += "    ";
         }
         for item in &fight.as_ref().unwrap().entities {
-            player_str += &item.name;
+            player_str += &format!("{} ({} HP)", item.name, item.hp);
             player_str += "    ";
         }
     }
     return Paragraph::new(vec![
         Spans::from(vec![Span::raw(player_str)]),
         Spans::from(vec![Span::raw(ini_str)]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            "enter - attack    esc - flee",
+            Style::default().fg(Color::Yellow),
+        )]),
     ])
     .alignment(Alignment::Center)
     .block(
@@ -273,6 +777,244 @@ fn render_fight(global_game: &mut Game) -> Paragraph {
     );
 }
 
+fn render_merchant<'a>(global_game: &'a Game) -> Paragraph<'a> {
+    let trade = match &global_game.trade {
+        Some(v) => v,
+        None => return Paragraph::new(""),
+    };
+    let merchant = &global_game.merchants[trade.merchant];
+    let selected_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+
+    let mut lines = vec![
+        Spans::from(vec![Span::raw(format!("Your gold: {}", global_game.gold))]),
+        Spans::from(vec![Span::raw("")]),
+        Spans::from(vec![Span::styled(
+            if trade.selling { "Sell" } else { "Buy" },
+            Style::default().fg(Color::Yellow),
+        )]),
+    ];
+
+    if trade.selling {
+        for (index, item) in global_game.inventory.inventory.iter().enumerate() {
+            let line = format!(
+                "{} - {} gold",
+                item.props.name,
+                merchant::sell_price(item.props.price)
+            );
+            lines.push(if index == trade.selected {
+                Spans::from(vec![Span::styled(line, selected_style)])
+            } else {
+                Spans::from(vec![Span::raw(line)])
+            });
+        }
+    } else {
+        for (index, entry) in merchant.stock.iter().enumerate() {
+            let props = global_game
+                .inventory
+                .get_item_props(&entry.item, &global_game.global_items);
+            let line = match props {
+                Some(props) => format!(
+                    "{} - {} gold ({} left)",
+                    props.name, props.price, entry.amount
+                ),
+                None => format!("unknown item {}", entry.item),
+            };
+            lines.push(if index == trade.selected {
+                Spans::from(vec![Span::styled(line, selected_style)])
+            } else {
+                Spans::from(vec![Span::raw(line)])
+            });
+        }
+    }
+
+    Paragraph::new(lines).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(merchant.name.as_str())
+            .border_type(BorderType::Plain),
+    )
+}
+
+fn render_loot<'a>(global_game: &'a Game) -> Paragraph<'a> {
+    let loot = match &global_game.loot {
+        Some(v) => v,
+        None => return Paragraph::new(""),
+    };
+    let selected_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+
+    let mut lines = vec![];
+    for (index, item_id) in global_game.chests[loot.chest].items.iter().enumerate() {
+        let line = match global_game
+            .inventory
+            .get_item_props(item_id, &global_game.global_items)
+        {
+            Some(props) => props.name,
+            None => format!("unknown item {}", item_id),
+        };
+        lines.push(if index == loot.selected {
+            Spans::from(vec![Span::styled(line, selected_style)])
+        } else {
+            Spans::from(vec![Span::raw(line)])
+        });
+    }
+
+    Paragraph::new(lines).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Chest")
+            .border_type(BorderType::Plain),
+    )
+}
+
+fn render_dialogue<'a>(global_game: &'a Game) -> Paragraph<'a> {
+    let (dialogue, node) = match (&global_game.dialogue, global_game.dialogue_node()) {
+        (Some(dialogue), Some(node)) => (dialogue, node),
+        _ => return Paragraph::new(""),
+    };
+    let selected_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+
+    let mut lines = vec![];
+    for line in node.text.lines() {
+        lines.push(Spans::from(vec![Span::raw(line)]));
+    }
+    lines.push(Spans::from(vec![Span::raw("")]));
+
+    let choices = global_game.dialogue_choices();
+    if choices.is_empty() {
+        lines.push(Spans::from(vec![Span::styled("[end]", selected_style)]));
+    }
+    for (index, choice) in choices.iter().enumerate() {
+        lines.push(if index == dialogue.selected {
+            Spans::from(vec![Span::styled(choice.text.as_str(), selected_style)])
+        } else {
+            Spans::from(vec![Span::raw(choice.text.as_str())])
+        });
+    }
+
+    Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(tui::widgets::Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(global_game.npcs[dialogue.npc].name.as_str())
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn render_journal<'a>(global_game: &'a Game) -> Paragraph<'a> {
+    let mut lines = vec![];
+    for state in &global_game.quest_log {
+        let quest = &global_game.quests[state.quest];
+        let title_style = if state.completed {
+            Style::default().fg(Color::Green)
+        } else {
+            Style::default().fg(Color::Yellow)
+        };
+        lines.push(Spans::from(vec![Span::styled(
+            quest.name.as_str(),
+            title_style,
+        )]));
+        if !quest.description.is_empty() {
+            lines.push(Spans::from(vec![Span::raw(quest.description.as_str())]));
+        }
+        for (objective, done) in quest.objectives.iter().zip(&state.done) {
+            lines.push(Spans::from(vec![Span::raw(format!(
+                "[{}] {}",
+                if *done { "x" } else { " " },
+                global_game.objective_text(objective)
+            ))]));
+        }
+        lines.push(Spans::from(vec![Span::raw("")]));
+    }
+    if lines.is_empty() {
+        lines.push(Spans::from(vec![Span::raw("No quests yet")]));
+    }
+
+    let entries = global_game.journal_entries();
+    if !entries.is_empty() {
+        lines.push(Spans::from(vec![Span::raw("")]));
+        lines.push(Spans::from(vec![Span::styled(
+            "Notes",
+            Style::default().fg(Color::Yellow),
+        )]));
+    }
+    for (title, entry) in entries {
+        lines.push(Spans::from(vec![Span::raw(format!(
+            "{}: {}",
+            title, entry
+        ))]));
+    }
+
+    Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(tui::widgets::Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title("Journal")
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn render_reader<'a>(global_game: &'a Game) -> Paragraph<'a> {
+    let reader = match &global_game.reader {
+        Some(reader) => reader,
+        None => return Paragraph::new(""),
+    };
+    let readable = &global_game.readables[reader.readable];
+
+    let lines: Vec<Spans> = readable
+        .text
+        .lines()
+        .map(|line| Spans::from(vec![Span::raw(line)]))
+        .collect();
+
+    Paragraph::new(lines)
+        .wrap(tui::widgets::Wrap { trim: false })
+        .scroll((reader.scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(format!("{} - {}", readable.kind.name(), readable.title))
+                .border_type(BorderType::Plain),
+        )
+}
+
+fn render_saves<'a>(global_game: &'a Game) -> Paragraph<'a> {
+    let menu = match &global_game.slot_menu {
+        Some(menu) => menu,
+        None => return Paragraph::new(""),
+    };
+    let selected_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+
+    let mut lines = vec![];
+    for (index, entry) in menu.entries.iter().enumerate() {
+        lines.push(if index == menu.selected {
+            Spans::from(vec![Span::styled(entry.label.as_str(), selected_style)])
+        } else {
+            Spans::from(vec![Span::raw(entry.label.as_str())])
+        });
+    }
+
+    Paragraph::new(lines).alignment(Alignment::Center).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(if menu.saving {
+                "Save game"
+            } else {
+                "Load game"
+            })
+            .border_type(BorderType::Plain),
+    )
+}
+
 fn get_map_as_paragraph(map: String) -> Paragraph<'static> {
     let mut map_spans = vec![];
     for line in map.lines() {
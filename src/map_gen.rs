@@ -1,51 +1,624 @@
+use crate::edges::{EdgeDefinition, Side};
+use crate::procgen::Generator;
+use crate::tiled::TiledMap;
 use crate::MapBlockTypes;
+use regex::Regex;
+use serde::de::{value::MapAccessDeserializer, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use thiserror::Error;
 
 pub type Map = Vec<Vec<MapBlockTypes>>;
 
-pub fn generate_map(mut map_str: String) -> Map {
+/// characters of grid maps and the tiles they stand for
+pub type Legend = HashMap<char, LegendTile>;
+
+/// a tile of the legend, written like `!door 3` or `!teleport [1, 9, 5]`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LegendTile {
+    Path,
+    Wall,
+    /// a path teleports can lead to by its name
+    Anchor(String),
+    NewMap(MapRef),
+    Teleport(TeleportTarget),
+    Item(usize),
+    Enemy(usize),
+    Gold(usize),
+    Merchant(usize),
+    Door(usize),
+    Lever(usize),
+    Chest(usize),
+    Trap(usize),
+    Shrine(usize),
+    Npc(usize),
+    Readable(usize),
+    Conditional(usize),
+    Flag(String),
+    StairsDown,
+    StairsUp,
+}
+
+/// a map by its index or its name
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum MapRef {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum TeleportTarget {
+    /// map, j and i like T(1,2,3)
+    Position([usize; 3]),
+    /// map and anchor like `crypt.entrance`
+    Anchor(String),
+}
+
+impl LegendTile {
+    pub fn raw(&self) -> Result<RawTile, TileError> {
+        let block = match self.clone() {
+            LegendTile::Path => MapBlockTypes::Path,
+            LegendTile::Wall => MapBlockTypes::NotWalkable,
+            LegendTile::Anchor(name) => return Ok(RawTile::Anchor(name)),
+            LegendTile::NewMap(MapRef::Index(map)) => MapBlockTypes::NewMapTrigger(map),
+            LegendTile::NewMap(MapRef::Name(map)) => return Ok(RawTile::NewMapTo(map)),
+            LegendTile::Teleport(TeleportTarget::Position([map, j, i])) => {
+                MapBlockTypes::TeleportTrigger(map, j, i)
+            }
+            LegendTile::Teleport(TeleportTarget::Anchor(target)) => {
+                return match target.split_once('.') {
+                    Some((map, anchor)) => Ok(RawTile::TeleportTo {
+                        map: map.to_string(),
+                        anchor: anchor.to_string(),
+                    }),
+                    None => Err(TileError::TeleportArity(target)),
+                }
+            }
+            LegendTile::Item(id) => MapBlockTypes::ItemTrigger(id),
+            LegendTile::Enemy(id) => MapBlockTypes::EnemyTrigger(id),
+            LegendTile::Gold(amount) => MapBlockTypes::GoldTrigger(amount),
+            LegendTile::Merchant(id) => MapBlockTypes::MerchantTrigger(id),
+            LegendTile::Door(id) => MapBlockTypes::Door(id, false),
+            LegendTile::Lever(id) => MapBlockTypes::Lever(id, false),
+            LegendTile::Chest(id) => MapBlockTypes::Chest(id),
+            LegendTile::Trap(id) => MapBlockTypes::Trap(id, false),
+            LegendTile::Shrine(id) => MapBlockTypes::Shrine(id),
+            LegendTile::Npc(id) => MapBlockTypes::Npc(id),
+            LegendTile::Readable(id) => MapBlockTypes::Readable(id),
+            LegendTile::Conditional(id) => MapBlockTypes::Conditional(id),
+            LegendTile::Flag(flag) => MapBlockTypes::FlagTrigger(flag),
+            LegendTile::StairsDown => MapBlockTypes::StairsDown,
+            LegendTile::StairsUp => MapBlockTypes::StairsUp,
+        };
+        Ok(RawTile::Block(block))
+    }
+}
+
+/// a tile as it is written, names of maps and anchors are resolved once all maps are read
+#[derive(Debug, Clone, PartialEq)]
+pub enum RawTile {
+    Block(MapBlockTypes),
+    /// a path with a name, like A(entrance)
+    Anchor(String),
+    /// new map trigger to a map by its name, like M(crypt)
+    NewMapTo(String),
+    /// teleport to an anchor, like T(crypt.entrance)
+    TeleportTo {
+        map: String,
+        anchor: String,
+    },
+}
+
+pub type RawMap = Vec<Vec<RawTile>>;
+
+/// names of the maps and of the anchors in them
+#[derive(Debug, Default)]
+pub struct Names {
+    maps: HashMap<String, usize>,
+    /// j and i of the anchors by map and name
+    anchors: HashMap<(usize, String), (usize, usize)>,
+}
+
+impl Names {
+    /// names of the maps only, a name used twice belongs to the first map
+    pub fn of_maps(definitions: &[MapDefinition]) -> Names {
+        let mut names = Names::default();
+        for (index, definition) in definitions.iter().enumerate() {
+            if let Some(name) = &definition.name {
+                if !names.add_map(name, index) {
+                    log::warn!("there is more than one map called {}", name);
+                }
+            }
+        }
+        names
+    }
+
+    /// returns false if another map already has the name, the first one keeps it
+    pub fn add_map(&mut self, name: &str, map: usize) -> bool {
+        if self.maps.contains_key(name) {
+            return false;
+        }
+        self.maps.insert(name.to_string(), map);
+        true
+    }
+
+    pub fn add_anchor(
+        &mut self,
+        map: usize,
+        name: &str,
+        j: usize,
+        i: usize,
+    ) -> Result<(), TileError> {
+        let key = (map, name.to_string());
+        if self.anchors.contains_key(&key) {
+            return Err(TileError::DuplicateAnchor(name.to_string()));
+        }
+        self.anchors.insert(key, (j, i));
+        Ok(())
+    }
+
+    fn add_anchors(&mut self, map: usize, raw: &RawMap) -> Result<(), MapError> {
+        for (j, row) in raw.iter().enumerate() {
+            for (i, tile) in row.iter().enumerate() {
+                if let RawTile::Anchor(name) = tile {
+                    self.add_anchor(map, name, j, i)
+                        .map_err(|source| MapError::Tile {
+                            map,
+                            row: j,
+                            column: i,
+                            source,
+                        })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// maps without a name can still be referenced by their index
+    pub fn map(&self, name: &str) -> Result<usize, TileError> {
+        match self.maps.get(name) {
+            Some(map) => Ok(*map),
+            None => name
+                .parse()
+                .map_err(|_| TileError::UnknownMap(name.to_string())),
+        }
+    }
+}
+
+impl RawTile {
+    /// the tile in the pipe syntax, parse_raw_tile reads it back
+    pub fn text(&self) -> String {
+        match self {
+            RawTile::Block(block) => block_text(block),
+            RawTile::Anchor(name) => format!("A({})", name),
+            RawTile::NewMapTo(map) => format!("M({})", map),
+            RawTile::TeleportTo { map, anchor } => format!("T({}.{})", map, anchor),
+        }
+    }
+
+    pub fn resolve(&self, names: &Names) -> Result<MapBlockTypes, TileError> {
+        match self {
+            RawTile::Block(block) => Ok(block.clone()),
+            RawTile::Anchor(_) => Ok(MapBlockTypes::Path),
+            RawTile::NewMapTo(map) => Ok(MapBlockTypes::NewMapTrigger(names.map(map)?)),
+            RawTile::TeleportTo { map, anchor } => {
+                let index = names.map(map)?;
+                match names.anchors.get(&(index, anchor.clone())) {
+                    Some(&(j, i)) => Ok(MapBlockTypes::TeleportTrigger(index, j, i)),
+                    None => Err(TileError::UnknownAnchor {
+                        map: map.clone(),
+                        anchor: anchor.clone(),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+/// a map of the scenario, written in the pipe syntax, as a grid of characters, made in tiled or generated
+#[derive(Debug, Clone)]
+pub struct MapDefinition {
+    pub name: Option<String>,
+    pub layout: Layout,
+    /// added to the legend of the scenario for this map only
+    pub legend: Legend,
+    /// maps behind the sides of this one
+    pub edges: HashMap<Side, EdgeDefinition>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Layout {
+    Pipes(String),
+    Grid(String),
+    Tiled(TiledMap),
+    Generated(Generator),
+}
+
+/// the long form of a map definition, a plain string is a map in the pipe syntax
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MapProps {
+    name: Option<String>,
+    tiles: Option<String>,
+    grid: Option<String>,
+    tiled: Option<String>,
+    generate: Option<Generator>,
+    #[serde(default)]
+    tileset: HashMap<u32, LegendTile>,
+    #[serde(default)]
+    legend: Legend,
+    #[serde(default)]
+    edges: HashMap<Side, EdgeDefinition>,
+}
+
+// a derived untagged enum would lose the yaml tags of the legend
+impl<'de> Deserialize<'de> for MapDefinition {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<MapDefinition, D::Error> {
+        struct DefinitionVisitor;
+
+        impl<'de> Visitor<'de> for DefinitionVisitor {
+            type Value = MapDefinition;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a map in the pipe syntax or a map with tiles, a grid, a tiled file or a generator")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<MapDefinition, E> {
+                Ok(MapDefinition {
+                    name: None,
+                    layout: Layout::Pipes(value.to_string()),
+                    legend: Legend::new(),
+                    edges: HashMap::new(),
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<MapDefinition, A::Error> {
+                let props = MapProps::deserialize(MapAccessDeserializer::new(map))?;
+                let layout = match (props.tiles, props.grid, props.tiled, props.generate) {
+                    (Some(tiles), None, None, None) => Layout::Pipes(tiles),
+                    (None, Some(grid), None, None) => Layout::Grid(grid),
+                    (None, None, Some(file), None) => Layout::Tiled(TiledMap {
+                        file,
+                        tileset: props.tileset,
+                        cells: vec![],
+                    }),
+                    (None, None, None, Some(generator)) => Layout::Generated(generator),
+                    _ => {
+                        return Err(serde::de::Error::custom(
+                            "a map needs either tiles, a grid, a tiled file or generate",
+                        ))
+                    }
+                };
+                Ok(MapDefinition {
+                    name: props.name,
+                    layout,
+                    legend: props.legend,
+                    edges: props.edges,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(DefinitionVisitor)
+    }
+}
+
+impl MapDefinition {
+    /// the legend of the scenario extended by the one of the map
+    pub fn legend(&self, scenario_legend: &Legend) -> Legend {
+        let mut legend = scenario_legend.clone();
+        legend.extend(self.legend.clone());
+        legend
+    }
+
+    pub fn raw_tiles(
+        &self,
+        map_index: usize,
+        scenario_legend: &Legend,
+    ) -> Result<RawMap, MapError> {
+        match &self.layout {
+            Layout::Pipes(map_str) => raw_pipe_map(map_index, map_str),
+            Layout::Grid(grid) => raw_grid_map(map_index, grid, &self.legend(scenario_legend)),
+            Layout::Tiled(tiled) => raw_tiled_map(map_index, tiled),
+            Layout::Generated(generator) => generator.generate(map_index),
+        }
+    }
+}
+
+/// a single tile which can not be understood
+#[derive(Debug, Error, PartialEq)]
+pub enum TileError {
+    #[error("unknown tile '{0}'")]
+    Unknown(String),
+    #[error("the number in tile '{0}' is too large")]
+    NumberTooLarge(String),
+    #[error(
+        "teleport '{0}' needs a map, j and i like T(1,2,3) or an anchor like T(crypt.entrance)"
+    )]
+    TeleportArity(String),
+    #[error("'{value}' in teleport '{token}' is not a number")]
+    TeleportNumber { token: String, value: String },
+    #[error("'{0}' is not in the legend")]
+    NotInLegend(char),
+    #[error("tile {0} is not in the tileset of the map")]
+    NotInTileset(u32),
+    #[error("there is no map called '{0}'")]
+    UnknownMap(String),
+    #[error("map {map} has no anchor '{anchor}'")]
+    UnknownAnchor { map: String, anchor: String },
+    #[error("anchor '{0}' is already used on this map")]
+    DuplicateAnchor(String),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum MapError {
+    /// a tile which can not be understood together with the place it was found
+    #[error("map {map}, row {row}, tile {column}: {source}")]
+    Tile {
+        map: usize,
+        row: usize,
+        column: usize,
+        source: TileError,
+    },
+    /// a map which can not be generated as a whole
+    #[error("map {map}: {message}")]
+    Generator { map: usize, message: String },
+}
+
+impl MapError {
+    /// the problem without the place it was found
+    pub fn problem(&self) -> String {
+        match self {
+            MapError::Tile { source, .. } => source.to_string(),
+            MapError::Generator { message, .. } => message.clone(),
+        }
+    }
+}
+
+/// the maps of generate_named_maps without their names
+#[cfg(test)]
+pub fn generate_maps(definitions: &[MapDefinition], legend: &Legend) -> Result<Vec<Map>, MapError> {
+    generate_named_maps(definitions, legend).map(|(maps, _)| maps)
+}
+
+/// reads all maps of a scenario and resolves the names used in them, the names are returned for
+/// tiles outside of the maps
+pub fn generate_named_maps(
+    definitions: &[MapDefinition],
+    legend: &Legend,
+) -> Result<(Vec<Map>, Names), MapError> {
+    let mut names = Names::of_maps(definitions);
+    let mut raw_maps = vec![];
+    for (index, definition) in definitions.iter().enumerate() {
+        let raw = definition.raw_tiles(index, legend)?;
+        names.add_anchors(index, &raw)?;
+        raw_maps.push(raw);
+    }
+
+    let maps = raw_maps
+        .iter()
+        .enumerate()
+        .map(|(index, raw)| resolve_map(index, raw, &names))
+        .collect::<Result<Vec<Map>, MapError>>()?;
+    log::debug!("maps: {:?}", maps);
+    Ok((maps, names))
+}
+
+/// a single map in the pipe syntax, it can only use its own anchors
+#[cfg(test)]
+pub fn generate_map(map_index: usize, map_str: String) -> Result<Map, MapError> {
+    let raw = raw_pipe_map(map_index, &map_str)?;
+    let mut names = Names::default();
+    names.add_anchors(map_index, &raw)?;
+    resolve_map(map_index, &raw, &names)
+}
+
+pub fn resolve_map(map_index: usize, raw: &RawMap, names: &Names) -> Result<Map, MapError> {
     let mut map = vec![];
-    map_str = map_str
+    for (j, raw_row) in raw.iter().enumerate() {
+        let mut row = vec![];
+        for (i, tile) in raw_row.iter().enumerate() {
+            row.push(tile.resolve(names).map_err(|source| MapError::Tile {
+                map: map_index,
+                row: j,
+                column: i,
+                source,
+            })?);
+        }
+        map.push(row);
+    }
+    Ok(map)
+}
+
+fn raw_pipe_map(map_index: usize, map_str: &str) -> Result<RawMap, MapError> {
+    let mut map = vec![];
+    for (j, row_tokens) in pipe_tokens(map_str).iter().enumerate() {
+        let mut row = vec![];
+        for (i, item) in row_tokens.iter().enumerate() {
+            row.push(parse_raw_tile(item).map_err(|source| MapError::Tile {
+                map: map_index,
+                row: j,
+                column: i,
+                source,
+            })?)
+        }
+        map.push(row);
+    }
+    Ok(map)
+}
+
+/// splits a map in the pipe syntax into its rows and tiles
+pub fn pipe_tokens(map_str: &str) -> Vec<Vec<String>> {
+    let map_str = map_str
         .replace("\r", "\n")
         .replace("\\n", "\n")
         .replace(" ", "\n");
+    log::debug!("map_str: {:?}", map_str.lines().collect::<Vec<&str>>());
+    map_str
+        .lines()
+        .map(|row_str| {
+            row_str
+                .split("|")
+                .filter(|x| x != &" " && x != &"")
+                .map(str::to_string)
+                .collect()
+        })
+        .collect()
+}
+
+/// every character of a grid is one tile, looked up in the legend
+fn raw_grid_map(map_index: usize, grid: &str, legend: &Legend) -> Result<RawMap, MapError> {
+    let mut map = vec![];
+    for (j, row_str) in grid_rows(grid).iter().enumerate() {
+        let mut row = vec![];
+        for (i, c) in row_str.chars().enumerate() {
+            row.push(parse_grid_tile(c, legend).map_err(|source| MapError::Tile {
+                map: map_index,
+                row: j,
+                column: i,
+                source,
+            })?);
+        }
+        map.push(row);
+    }
+    Ok(map)
+}
 
-    for (_, row_str) in map_str.lines().collect::<Vec<&str>>().iter().enumerate() {
-        let mut row: Vec<MapBlockTypes> = vec![];
-        for (_, item) in row_str
-            .split("|")
-            .filter(|x| x != &" " && x != &"")
-            .enumerate()
-        {
-            row.push(get_block_type(item))
+/// the cells of a map made in tiled, which has to be loaded before
+fn raw_tiled_map(map_index: usize, tiled: &TiledMap) -> Result<RawMap, MapError> {
+    let mut map = vec![];
+    for (j, cells) in tiled.cells.iter().enumerate() {
+        let mut row = vec![];
+        for (i, cell) in cells.iter().enumerate() {
+            row.push(cell.raw(&tiled.tileset).map_err(|source| MapError::Tile {
+                map: map_index,
+                row: j,
+                column: i,
+                source,
+            })?);
         }
         map.push(row);
     }
-    println!("map_str: {:?}", map_str.lines().collect::<Vec<&str>>());
-    println!("map: {:?}", map);
-    return map;
+    Ok(map)
+}
+
+/// rows of a grid map, empty lines and spaces at the end of a row are left out
+pub fn grid_rows(grid: &str) -> Vec<&str> {
+    grid.lines()
+        .map(str::trim_end)
+        .filter(|row| !row.is_empty())
+        .collect()
+}
+
+/// `#` and `x` are walls, `.` and `_` paths and `>` and `<` stairs unless the legend says otherwise
+pub fn parse_grid_tile(c: char, legend: &Legend) -> Result<RawTile, TileError> {
+    if let Some(tile) = legend.get(&c) {
+        return tile.raw();
+    }
+    match c {
+        '#' | 'x' => Ok(RawTile::Block(MapBlockTypes::NotWalkable)),
+        '.' | '_' => Ok(RawTile::Block(MapBlockTypes::Path)),
+        '>' => Ok(RawTile::Block(MapBlockTypes::StairsDown)),
+        '<' => Ok(RawTile::Block(MapBlockTypes::StairsUp)),
+        _ => Err(TileError::NotInLegend(c)),
+    }
+}
+
+/// like parse_block_type, but also understands anchors and references to them
+pub fn parse_raw_tile(str: &str) -> Result<RawTile, TileError> {
+    let regexes = tile_regexes();
+    if let Some(caps) = regexes.anchor.captures(str) {
+        return Ok(RawTile::Anchor(caps[1].to_string()));
+    }
+    if let Some(caps) = regexes.new_map_to.captures(str) {
+        return Ok(RawTile::NewMapTo(caps[1].to_string()));
+    }
+    if let Some(caps) = regexes.teleport_to.captures(str) {
+        return Ok(RawTile::TeleportTo {
+            map: caps[1].to_string(),
+            anchor: caps[2].to_string(),
+        });
+    }
+    parse_block_type(str).map(RawTile::Block)
 }
 
-fn get_block_type(str: &str) -> MapBlockTypes {
-    let new_maps_trigger_re = regex::Regex::new(r"M(\d+)").unwrap();
-    let new_maps_trigger_caps = new_maps_trigger_re.captures(str);
-    if new_maps_trigger_caps.is_some() {
-        return MapBlockTypes::NewMapTrigger(extract_first_match(new_maps_trigger_caps.unwrap()));
+/// like parse_block_type, but turns tiles which are not understood into walls
+pub fn get_block_type(str: &str) -> MapBlockTypes {
+    match parse_block_type(str) {
+        Ok(block) => block,
+        Err(e) => {
+            log::warn!("{}", e);
+            MapBlockTypes::NotWalkable
+        }
     }
+}
+
+/// builds the block of a tile from the number it holds
+type TileCtor = fn(usize) -> MapBlockTypes;
+
+/// tiles holding a single number, checked in this order
+const NUMBERED_TILES: [(&str, TileCtor); 13] = [
+    (r"^G(\d+)$", MapBlockTypes::GoldTrigger),
+    (r"^S(\d+)$", MapBlockTypes::MerchantTrigger),
+    (r"^D(\d+)$", |id| MapBlockTypes::Door(id, false)),
+    (r"^L(\d+)$", |id| MapBlockTypes::Lever(id, false)),
+    (r"^C(\d+)$", MapBlockTypes::Chest),
+    (r"^P(\d+)$", |id| MapBlockTypes::Trap(id, false)),
+    (r"^H(\d+)$", MapBlockTypes::Shrine),
+    (r"^N(\d+)$", MapBlockTypes::Npc),
+    (r"^R(\d+)$", MapBlockTypes::Readable),
+    (r"^\?(\d+)$", MapBlockTypes::Conditional),
+    (r"M(\d+)", MapBlockTypes::NewMapTrigger),
+    (r"I(\d+)", MapBlockTypes::ItemTrigger),
+    (r"E(\d+)", MapBlockTypes::EnemyTrigger),
+];
+
+struct TileRegexes {
+    numbered: Vec<(Regex, TileCtor)>,
+    flag_trigger: Regex,
+    tp_trigger: Regex,
+    anchor: Regex,
+    new_map_to: Regex,
+    teleport_to: Regex,
+}
 
-    let item_trigger_re = regex::Regex::new(r"I(\d+)").unwrap();
-    let item_trigger_caps = item_trigger_re.captures(str);
-    if item_trigger_caps.is_some() {
-        return MapBlockTypes::ItemTrigger(extract_first_match(item_trigger_caps.unwrap()));
+/// the regexes are compiled once, maps have a lot of tiles
+fn tile_regexes() -> &'static TileRegexes {
+    static REGEXES: OnceLock<TileRegexes> = OnceLock::new();
+    REGEXES.get_or_init(|| TileRegexes {
+        numbered: NUMBERED_TILES
+            .iter()
+            .map(|(pattern, block)| (Regex::new(pattern).unwrap(), *block))
+            .collect(),
+        flag_trigger: Regex::new(r"^F\((\w+)\)$").unwrap(),
+        tp_trigger: Regex::new(r"T(.+)").unwrap(),
+        anchor: Regex::new(r"^A\((\w+)\)$").unwrap(),
+        new_map_to: Regex::new(r"^M\((\w+)\)$").unwrap(),
+        teleport_to: Regex::new(r"^T\((\w+)\.(\w+)\)$").unwrap(),
+    })
+}
+
+pub fn parse_block_type(str: &str) -> Result<MapBlockTypes, TileError> {
+    let regexes = tile_regexes();
+
+    if let Some(caps) = regexes.flag_trigger.captures(str) {
+        return Ok(MapBlockTypes::FlagTrigger(
+            caps.get(1).unwrap().as_str().to_string(),
+        ));
     }
 
-    let enemy_trigger_re = regex::Regex::new(r"E(\d+)").unwrap();
-    let enemy_trigger_caps = enemy_trigger_re.captures(str);
-    if enemy_trigger_caps.is_some() {
-        return MapBlockTypes::EnemyTrigger(extract_first_match(enemy_trigger_caps.unwrap()));
+    for (re, block) in &regexes.numbered {
+        if let Some(caps) = re.captures(str) {
+            let number = extract_first_match(caps)
+                .map_err(|_| TileError::NumberTooLarge(str.to_string()))?;
+            return Ok(block(number));
+        }
     }
 
-    let tp_trigger_re = regex::Regex::new(r"T(.+)").unwrap();
-    let tp_trigger_caps = tp_trigger_re.captures(str);
+    let tp_trigger_caps = regexes.tp_trigger.captures(str);
     if tp_trigger_caps.is_some() {
         let input = tp_trigger_caps.unwrap().get(1).unwrap().as_str();
 
@@ -53,23 +626,90 @@ fn get_block_type(str: &str) -> MapBlockTypes {
             .trim_matches(|c| c == '(' || c == ')')
             .split(',')
             .collect();
+        if values.len() != 3 {
+            return Err(TileError::TeleportArity(str.to_string()));
+        }
+        let mut numbers = vec![];
+        for value in values {
+            numbers.push(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| TileError::TeleportNumber {
+                        token: str.to_string(),
+                        value: value.to_string(),
+                    })?,
+            );
+        }
 
-        return MapBlockTypes::TeleportTrigger(
-            values[0].parse().unwrap(),
-            values[1].parse().unwrap(),
-            values[2].parse().unwrap(),
-        );
+        return Ok(MapBlockTypes::TeleportTrigger(
+            numbers[0], numbers[1], numbers[2],
+        ));
     }
 
     match str {
-        "x" => MapBlockTypes::NotWalkable,
-        "_" => MapBlockTypes::Path,
-        _ => MapBlockTypes::NotWalkable,
+        "x" => Ok(MapBlockTypes::NotWalkable),
+        "_" => Ok(MapBlockTypes::Path),
+        ">" => Ok(MapBlockTypes::StairsDown),
+        "<" => Ok(MapBlockTypes::StairsUp),
+        _ => Err(TileError::Unknown(str.to_string())),
     }
 }
 
-fn extract_first_match(caps: regex::Captures) -> usize {
-    return caps.get(1).unwrap().as_str().to_string().parse().unwrap();
+fn extract_first_match(caps: regex::Captures) -> Result<usize, std::num::ParseIntError> {
+    return caps.get(1).unwrap().as_str().to_string().parse();
+}
+
+/// a block in the pipe syntax, open doors and pulled levers are written closed
+pub fn block_text(block: &MapBlockTypes) -> String {
+    match block {
+        MapBlockTypes::Path => "_".to_string(),
+        MapBlockTypes::NotWalkable => "x".to_string(),
+        MapBlockTypes::NewMapTrigger(map) => format!("M{}", map),
+        MapBlockTypes::TeleportTrigger(map, j, i) => format!("T({},{},{})", map, j, i),
+        MapBlockTypes::EnemyTrigger(id) => format!("E{}", id),
+        MapBlockTypes::ItemTrigger(id) => format!("I{}", id),
+        MapBlockTypes::GoldTrigger(amount) => format!("G{}", amount),
+        MapBlockTypes::MerchantTrigger(id) => format!("S{}", id),
+        MapBlockTypes::Door(id, _) => format!("D{}", id),
+        MapBlockTypes::Lever(id, _) => format!("L{}", id),
+        MapBlockTypes::Chest(id) => format!("C{}", id),
+        MapBlockTypes::Trap(id, _) => format!("P{}", id),
+        MapBlockTypes::Shrine(id) => format!("H{}", id),
+        MapBlockTypes::Npc(id) => format!("N{}", id),
+        MapBlockTypes::Conditional(id) => format!("?{}", id),
+        MapBlockTypes::FlagTrigger(flag) => format!("F({})", flag),
+        MapBlockTypes::Readable(id) => format!("R{}", id),
+        MapBlockTypes::StairsDown => ">".to_string(),
+        MapBlockTypes::StairsUp => "<".to_string(),
+    }
+}
+
+/// the two characters a block is drawn with
+pub fn block_symbol(block: &MapBlockTypes) -> &'static str {
+    match block {
+        MapBlockTypes::Path => "  ",
+        MapBlockTypes::NotWalkable => "XX",
+        MapBlockTypes::TeleportTrigger(_, _, _) => "TT",
+        MapBlockTypes::ItemTrigger(_) => "@@",
+        MapBlockTypes::EnemyTrigger(_) => "##",
+        MapBlockTypes::GoldTrigger(_) => "$$",
+        MapBlockTypes::MerchantTrigger(_) => "&&",
+        MapBlockTypes::Door(_, false) => "[]",
+        MapBlockTypes::Door(_, true) => "  ",
+        MapBlockTypes::Lever(_, false) => "/o",
+        MapBlockTypes::Lever(_, true) => "o\\",
+        MapBlockTypes::Chest(_) => "==",
+        MapBlockTypes::Trap(_, false) => "  ",
+        MapBlockTypes::Trap(_, true) => "^^",
+        MapBlockTypes::Shrine(_) => "++",
+        MapBlockTypes::Npc(_) => "**",
+        MapBlockTypes::Conditional(_) => "??",
+        MapBlockTypes::Readable(_) => "!!",
+        MapBlockTypes::StairsDown => ">>",
+        MapBlockTypes::StairsUp => "<<",
+        _ => "  ",
+    }
 }
 
 pub fn visulize_map(map: &Map, player_pos: Option<&crate::Pos>) -> String {
@@ -77,14 +717,7 @@ pub fn visulize_map(map: &Map, player_pos: Option<&crate::Pos>) -> String {
     for (j, row) in map.iter().enumerate() {
         let mut row_str = "".to_string();
         for (i, item) in row.iter().enumerate() {
-            let mut get_symbol = match &item {
-                MapBlockTypes::Path => "  ",
-                MapBlockTypes::NotWalkable => "XX",
-                MapBlockTypes::TeleportTrigger(_, _, _) => "TT",
-                MapBlockTypes::ItemTrigger(_) => "@@",
-                MapBlockTypes::EnemyTrigger(_) => "##",
-                _ => "  ",
-            };
+            let mut get_symbol = block_symbol(item);
             if player_pos.is_some() && j == player_pos.unwrap().j && i == player_pos.unwrap().i {
                 get_symbol = "<>";
             }
@@ -97,7 +730,12 @@ pub fn visulize_map(map: &Map, player_pos: Option<&crate::Pos>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::map_gen::{extract_first_match, generate_map, get_block_type, visulize_map};
+    use crate::game::MapBlockTypes;
+    use crate::map_gen::{
+        generate_map, generate_maps, get_block_type, parse_block_type, visulize_map, Legend,
+        MapDefinition, MapError, TileError,
+    };
+    use crate::map_gen::{parse_raw_tile, pipe_tokens};
 
     #[test]
     fn test_get_block_types() {
@@ -106,6 +744,31 @@ mod tests {
         assert_eq!(b, crate::MapBlockTypes::NewMapTrigger(1));
         let b = get_block_type("I0");
         assert_eq!(b, crate::MapBlockTypes::ItemTrigger(0));
+        let b = get_block_type("G25");
+        assert_eq!(b, crate::MapBlockTypes::GoldTrigger(25));
+        let b = get_block_type("S0");
+        assert_eq!(b, crate::MapBlockTypes::MerchantTrigger(0));
+        let b = get_block_type("D3");
+        assert_eq!(b, crate::MapBlockTypes::Door(3, false));
+        let b = get_block_type("L1");
+        assert_eq!(b, crate::MapBlockTypes::Lever(1, false));
+        let b = get_block_type("C2");
+        assert_eq!(b, crate::MapBlockTypes::Chest(2));
+        let b = get_block_type("P4");
+        assert_eq!(b, crate::MapBlockTypes::Trap(4, false));
+        let b = get_block_type("H0");
+        assert_eq!(b, crate::MapBlockTypes::Shrine(0));
+        let b = get_block_type("N1");
+        assert_eq!(b, crate::MapBlockTypes::Npc(1));
+        let b = get_block_type("R3");
+        assert_eq!(b, crate::MapBlockTypes::Readable(3));
+        let b = get_block_type("?2");
+        assert_eq!(b, crate::MapBlockTypes::Conditional(2));
+        let b = get_block_type("F(boss_room)");
+        assert_eq!(
+            b,
+            crate::MapBlockTypes::FlagTrigger("boss_room".to_string())
+        );
     }
 
     #[test]
@@ -133,6 +796,7 @@ mod tests {
     #[test]
     fn test_map_gen() {
         let map = generate_map(
+            0,
             "|x|M1|M1|x|x|x|x|x|x|x|
 |x|_|_|x|x|_|_|_|_|x|
 |x|_|_|x|x|_|I0|_|_|x|
@@ -144,7 +808,8 @@ mod tests {
 |x|x|x|x|_|_|x|x|x|x|
 |x|x|x|x|_|_|x|x|x|x|"
                 .to_string(),
-        );
+        )
+        .unwrap();
         println!("{:?}", map);
         visulize_map(&map, None)
             .lines()
@@ -166,4 +831,131 @@ XXXXXXXX    XXXXXXXX\n"
                 .to_string()
         )
     }
+    #[test]
+    fn test_map_error() {
+        let error = generate_map(2, "|x|_|\n|_|T(1,2)|".to_string()).unwrap_err();
+
+        assert_eq!(
+            error,
+            MapError::Tile {
+                map: 2,
+                row: 1,
+                column: 1,
+                source: TileError::TeleportArity("T(1,2)".to_string()),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "map 2, row 1, tile 1: teleport 'T(1,2)' needs a map, j and i like T(1,2,3) or an anchor like T(crypt.entrance)"
+        );
+        assert_eq!(
+            parse_block_type("Z9"),
+            Err(TileError::Unknown("Z9".to_string()))
+        );
+        assert_eq!(
+            parse_block_type("G99999999999999999999999"),
+            Err(TileError::NumberTooLarge(
+                "G99999999999999999999999".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_grid_map() {
+        let definition: MapDefinition = serde_yaml::from_str(
+            "grid: |
+    #.D#
+    #$T.
+legend:
+    D: !door 3
+    T: !teleport [1, 2, 3]",
+        )
+        .unwrap();
+        let legend: Legend = serde_yaml::from_str("$: !gold 5\nD: wall").unwrap();
+
+        assert_eq!(
+            generate_maps(std::slice::from_ref(&definition), &legend).unwrap()[0],
+            vec![
+                vec![
+                    MapBlockTypes::NotWalkable,
+                    MapBlockTypes::Path,
+                    MapBlockTypes::Door(3, false),
+                    MapBlockTypes::NotWalkable,
+                ],
+                vec![
+                    MapBlockTypes::NotWalkable,
+                    MapBlockTypes::GoldTrigger(5),
+                    MapBlockTypes::TeleportTrigger(1, 2, 3),
+                    MapBlockTypes::Path,
+                ],
+            ]
+        );
+        assert_eq!(
+            generate_maps(&[definition], &Legend::new()).unwrap_err(),
+            MapError::Tile {
+                map: 0,
+                row: 1,
+                column: 1,
+                source: TileError::NotInLegend('$'),
+            }
+        );
+
+        let pipes: MapDefinition = serde_yaml::from_str("'|x|_|'").unwrap();
+        assert_eq!(
+            generate_maps(&[pipes], &legend).unwrap()[0],
+            vec![vec![MapBlockTypes::NotWalkable, MapBlockTypes::Path]]
+        );
+    }
+
+    #[test]
+    fn test_tile_text() {
+        let map = "|x|_|I3|E0|G25|S1|D2|L0|C4| |P1|H0|N2|R5|?1|M3|>|<|F(seen)|T(1,2,3)|A(door)|M(crypt)|T(crypt.door)|";
+        for row in pipe_tokens(map) {
+            for token in row {
+                assert_eq!(parse_raw_tile(&token).unwrap().text(), token);
+            }
+        }
+    }
+
+    #[test]
+    fn test_named_maps() {
+        let definitions: Vec<MapDefinition> = serde_yaml::from_str(
+            "- name: hall
+  tiles: '|_|T(crypt.entrance)|M(crypt)|'
+- name: crypt
+  grid: |
+    #e.
+  legend:
+    e: !anchor entrance
+    h: !teleport hall.door",
+        )
+        .unwrap();
+        let maps = generate_maps(&definitions, &Legend::new()).unwrap();
+
+        assert_eq!(
+            maps[0],
+            vec![vec![
+                MapBlockTypes::Path,
+                MapBlockTypes::TeleportTrigger(1, 0, 1),
+                MapBlockTypes::NewMapTrigger(1),
+            ]]
+        );
+        assert_eq!(maps[1][0][1], MapBlockTypes::Path);
+
+        let unknown: Vec<MapDefinition> =
+            serde_yaml::from_str("- '|A(a)|T(0.a)|T(0.b)|'\n- '|M(cellar)|'").unwrap();
+        assert_eq!(
+            generate_maps(&unknown, &Legend::new()).unwrap_err(),
+            MapError::Tile {
+                map: 0,
+                row: 0,
+                column: 2,
+                source: TileError::UnknownAnchor {
+                    map: "0".to_string(),
+                    anchor: "b".to_string()
+                },
+            }
+        );
+        assert!(serde_yaml::from_str::<MapDefinition>("name: empty").is_err());
+    }
 }
@@ -0,0 +1,214 @@
+use crate::game::Game;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Merchant {
+    pub id: usize,
+    pub name: String,
+    /// items the merchant has for sale
+    #[serde(default)]
+    pub stock: Vec<StockEntry>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StockEntry {
+    /// item_id in global_items
+    pub item: usize,
+    pub amount: usize,
+}
+
+/// state of the currently open buy/sell screen
+#[derive(Debug, Clone)]
+pub struct Trade {
+    /// index in Game::merchants
+    pub merchant: usize,
+    pub selected: usize,
+    pub selling: bool,
+}
+
+pub fn sell_price(price: usize) -> usize {
+    price / 2
+}
+
+impl Game {
+    pub fn open_trade(&mut self, merchant_id: usize) {
+        let merchant = match self.merchants.iter().position(|m| m.id == merchant_id) {
+            Some(v) => v,
+            None => {
+                log::warn!("merchant {} not found", merchant_id);
+                return;
+            }
+        };
+        self.info_queue.queue(
+            "Merchant".to_string(),
+            format!("{} wants to trade", self.merchants[merchant].name),
+        );
+        self.trade = Some(Trade {
+            merchant,
+            selected: 0,
+            selling: false,
+        });
+        self.active_menu_item = crate::MenuItem::Merchant;
+    }
+
+    pub fn close_trade(&mut self) {
+        self.trade = None;
+        self.active_menu_item = crate::MenuItem::Game;
+    }
+
+    /// number of entries in the list shown on the active side of the trade screen
+    fn trade_list_len(&self, trade: &Trade) -> usize {
+        if trade.selling {
+            self.inventory.inventory.len()
+        } else {
+            self.merchants[trade.merchant].stock.len()
+        }
+    }
+
+    pub fn trade_up(&mut self) {
+        if let Some(trade) = self.trade.as_mut() {
+            trade.selected = trade.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn trade_down(&mut self) {
+        if let Some(mut trade) = self.trade.clone() {
+            if trade.selected + 1 < self.trade_list_len(&trade) {
+                trade.selected += 1;
+            }
+            self.trade = Some(trade);
+        }
+    }
+
+    pub fn trade_switch_side(&mut self) {
+        if let Some(trade) = self.trade.as_mut() {
+            trade.selling = !trade.selling;
+            trade.selected = 0;
+        }
+    }
+
+    pub fn trade_confirm(&mut self) {
+        let trade = match self.trade.clone() {
+            Some(v) => v,
+            None => return,
+        };
+        let result = if trade.selling {
+            self.sell(&trade)
+        } else {
+            self.buy(&trade)
+        };
+        match result {
+            Ok(message) => self.info_queue.queue("Merchant".to_string(), message),
+            Err(e) => self.info_queue.queue("Merchant".to_string(), e),
+        }
+
+        // keep the selection inside the list after the entry was removed
+        let len = self.trade_list_len(&trade);
+        if let Some(trade) = self.trade.as_mut() {
+            trade.selected = trade.selected.min(len.saturating_sub(1));
+        }
+    }
+
+    fn buy(&mut self, trade: &Trade) -> Result<String, String> {
+        let entry = self.merchants[trade.merchant]
+            .stock
+            .get(trade.selected)
+            .ok_or("Nothing selected".to_string())?
+            .clone();
+        let props = self
+            .inventory
+            .get_item_props(&entry.item, &self.global_items)
+            .ok_or("Item not found".to_string())?;
+
+        if entry.amount == 0 {
+            return Err(format!("{} is sold out", props.name));
+        }
+        if self.gold < props.price {
+            return Err(format!("You can not afford a {}", props.name));
+        }
+
+        self.pick_up_item(entry.item)?;
+        self.gold -= props.price;
+        self.merchants[trade.merchant].stock[trade.selected].amount -= 1;
+        Ok(format!(
+            "You bought a {} for {} gold",
+            props.name, props.price
+        ))
+    }
+
+    fn sell(&mut self, trade: &Trade) -> Result<String, String> {
+        let sold = self
+            .inventory
+            .remove_item(trade.selected)
+            .ok_or("Nothing selected".to_string())?;
+        let price = sell_price(sold.props.price);
+        self.gold += price;
+
+        let stock = &mut self.merchants[trade.merchant].stock;
+        match stock.iter_mut().find(|entry| entry.item == sold.props.id) {
+            Some(entry) => entry.amount += 1,
+            None => stock.push(StockEntry {
+                item: sold.props.id,
+                amount: 1,
+            }),
+        }
+        Ok(format!("You sold a {} for {} gold", sold.props.name, price))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+
+    fn trading_game() -> Game {
+        let mut game = Game::from_yaml(
+            "
+merchants:
+    - id: 0
+      name: Grimbold
+      stock:
+          - {item: 1, amount: 2}
+          - {item: 2, amount: 1}
+",
+        );
+        game.gold = 10;
+        game.open_trade(game.merchants[0].id);
+        game
+    }
+
+    #[test]
+    fn test_buy_item() {
+        let mut game = trading_game();
+
+        game.trade_confirm();
+
+        // boots cost 6
+        assert_eq!(game.gold, 4);
+        assert_eq!(game.inventory.inventory.len(), 1);
+        assert_eq!(game.merchants[0].stock[0].amount, 1);
+    }
+
+    #[test]
+    fn test_buy_without_gold() {
+        let mut game = trading_game();
+        game.gold = 0;
+
+        game.trade_confirm();
+
+        assert_eq!(game.inventory.inventory.len(), 0);
+    }
+
+    #[test]
+    fn test_sell_item() {
+        let mut game = trading_game();
+        game.inventory.add_item(0, &game.global_items).unwrap();
+        game.trade_switch_side();
+
+        game.trade_confirm();
+
+        // half of the 12 gold the sword costs
+        assert_eq!(game.gold, 16);
+        assert_eq!(game.inventory.inventory.len(), 0);
+        assert!(game.merchants[0].stock.iter().any(|entry| entry.item == 0));
+    }
+}
@@ -0,0 +1,527 @@
+use crate::game::MapBlockTypes;
+use crate::map_gen::{LegendTile, MapError, RawMap, RawTile, TeleportTarget};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+
+/// a map made by the game instead of by hand, the same settings always make the same map
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Generator {
+    pub algorithm: Algorithm,
+    pub width: usize,
+    pub height: usize,
+    /// mixed with the index of the map, so maps with the same settings differ
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default)]
+    pub items: Vec<Weighted>,
+    #[serde(default)]
+    pub item_count: usize,
+    #[serde(default)]
+    pub enemies: Vec<Weighted>,
+    #[serde(default)]
+    pub enemy_count: usize,
+    /// added to item_count on every level of the dungeon below the surface
+    #[serde(default)]
+    pub items_per_depth: usize,
+    /// added to enemy_count on every level of the dungeon below the surface
+    #[serde(default)]
+    pub enemies_per_depth: usize,
+    /// teleports placed on the map, like `[0, 3, 4]` or `crypt.entrance`
+    #[serde(default)]
+    pub exits: Vec<TeleportTarget>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Algorithm {
+    /// rooms in a binary space partition, joined by corridors
+    Rooms,
+    /// cellular automata, only the largest cave is kept
+    Caves,
+}
+
+/// an item or enemy id and how likely it is picked
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Weighted {
+    pub id: usize,
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    /// shallowest level of the dungeon the entry is picked on, maps of the scenario are depth 0
+    #[serde(default)]
+    pub min_depth: usize,
+    /// deepest level of the dungeon the entry is picked on
+    pub max_depth: Option<usize>,
+}
+
+impl Weighted {
+    fn at_depth(&self, depth: usize) -> bool {
+        self.min_depth <= depth && self.max_depth.map_or(true, |max| depth <= max)
+    }
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// the anchor every generated map has, other maps teleport to it like `T(caves.entrance)`
+pub const ENTRANCE: &str = "entrance";
+
+const SMALLEST_ROOM: usize = 3;
+
+impl Generator {
+    pub fn generate(&self, map_index: usize) -> Result<RawMap, MapError> {
+        self.build(
+            map_index,
+            0,
+            vec![RawTile::Anchor(ENTRANCE.to_string())],
+            &self.exits,
+        )
+    }
+
+    /// a level of the dungeon, the player arrives on its stairs up.
+    /// levels are joined by stairs only, the exits are left out
+    pub fn generate_level(
+        &self,
+        map_index: usize,
+        depth: usize,
+        stairs_down: bool,
+    ) -> Result<RawMap, MapError> {
+        let mut stairs = vec![RawTile::Block(MapBlockTypes::StairsUp)];
+        if stairs_down {
+            stairs.push(RawTile::Block(MapBlockTypes::StairsDown));
+        }
+        self.build(map_index, depth, stairs, &[])
+    }
+
+    fn build(
+        &self,
+        map_index: usize,
+        depth: usize,
+        mut tiles: Vec<RawTile>,
+        exit_targets: &[TeleportTarget],
+    ) -> Result<RawMap, MapError> {
+        let error = |message| MapError::Generator {
+            map: map_index,
+            message,
+        };
+        if self.width < SMALLEST_ROOM + 2 || self.height < SMALLEST_ROOM + 2 {
+            return Err(error(format!(
+                "a generated map needs to be at least {0}x{0}",
+                SMALLEST_ROOM + 2
+            )));
+        }
+        let mut rng = StdRng::seed_from_u64(
+            self.seed ^ (map_index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15),
+        );
+        let floor = match self.algorithm {
+            Algorithm::Rooms => rooms(self.width, self.height, &mut rng),
+            Algorithm::Caves => caves(self.width, self.height, &mut rng),
+        };
+
+        let mut free: Vec<(usize, usize)> = vec![];
+        for (j, row) in floor.iter().enumerate() {
+            for (i, is_floor) in row.iter().enumerate() {
+                if *is_floor {
+                    free.push((j, i));
+                }
+            }
+        }
+        free.shuffle(&mut rng);
+
+        // walking onto a teleport leaves the map, so none may cut off a part of it
+        let mut exits = vec![];
+        for exit in exit_targets {
+            let tile = LegendTile::Teleport(exit.clone())
+                .raw()
+                .map_err(|e| error(format!("exits: {}", e)))?;
+            let blocked: Vec<(usize, usize)> = exits.iter().map(|(_, pos)| *pos).collect();
+            let at = free
+                .iter()
+                .position(|pos| connected(&floor, &[blocked.as_slice(), &[*pos]].concat()))
+                .ok_or_else(|| {
+                    error(
+                        "there is no place for an exit which does not cut off a part of the map"
+                            .to_string(),
+                    )
+                })?;
+            exits.push((tile, free.remove(at)));
+        }
+
+        let item_count = self.item_count + self.items_per_depth * depth;
+        let enemy_count = self.enemy_count + self.enemies_per_depth * depth;
+        tiles.extend(
+            pick(&self.items, item_count, depth, &mut rng)
+                .map_err(|e| error(format!("items: {}", e)))?
+                .into_iter()
+                .map(|id| RawTile::Block(MapBlockTypes::ItemTrigger(id))),
+        );
+        tiles.extend(
+            pick(&self.enemies, enemy_count, depth, &mut rng)
+                .map_err(|e| error(format!("enemies: {}", e)))?
+                .into_iter()
+                .map(|id| RawTile::Block(MapBlockTypes::EnemyTrigger(id))),
+        );
+        if tiles.len() > free.len() {
+            return Err(error(format!(
+                "{} things do not fit onto the {} free tiles of the map",
+                tiles.len() + exits.len(),
+                free.len() + exits.len()
+            )));
+        }
+
+        let mut map: RawMap = floor
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|is_floor| {
+                        RawTile::Block(if *is_floor {
+                            MapBlockTypes::Path
+                        } else {
+                            MapBlockTypes::NotWalkable
+                        })
+                    })
+                    .collect()
+            })
+            .collect();
+        for (tile, (j, i)) in exits.into_iter().chain(tiles.into_iter().zip(free)) {
+            map[j][i] = tile;
+        }
+        Ok(map)
+    }
+}
+
+/// whether all floor tiles except the blocked ones can be reached from each other
+fn connected(floor: &[Vec<bool>], blocked: &[(usize, usize)]) -> bool {
+    let open = |j: usize, i: usize| floor[j][i] && !blocked.contains(&(j, i));
+    let mut tiles = vec![];
+    for (j, row) in floor.iter().enumerate() {
+        for i in 0..row.len() {
+            if open(j, i) {
+                tiles.push((j, i));
+            }
+        }
+    }
+    let start = match tiles.first() {
+        Some(start) => *start,
+        None => return true,
+    };
+    let mut seen = vec![vec![false; floor[0].len()]; floor.len()];
+    seen[start.0][start.1] = true;
+    let mut count = 1;
+    let mut stack = vec![start];
+    while let Some((j, i)) = stack.pop() {
+        // the outer row and column are walls, so there is no neighbour outside of the map
+        for (nj, ni) in [(j - 1, i), (j + 1, i), (j, i - 1), (j, i + 1)] {
+            if open(nj, ni) && !seen[nj][ni] {
+                seen[nj][ni] = true;
+                count += 1;
+                stack.push((nj, ni));
+            }
+        }
+    }
+    count == tiles.len()
+}
+
+/// `count` ids from the entries of the pool for the depth, the ones with a larger weight more often
+fn pick(
+    pool: &[Weighted],
+    count: usize,
+    depth: usize,
+    rng: &mut StdRng,
+) -> Result<Vec<usize>, String> {
+    if count == 0 {
+        return Ok(vec![]);
+    }
+    let pool: Vec<&Weighted> = pool.iter().filter(|entry| entry.at_depth(depth)).collect();
+    let weights = WeightedIndex::new(pool.iter().map(|entry| entry.weight)).map_err(|_| {
+        let mut message = "the pool needs at least one entry with a weight above 0".to_string();
+        if depth > 0 {
+            message += &format!(" for depth {}", depth);
+        }
+        message
+    })?;
+    Ok((0..count).map(|_| pool[weights.sample(rng)].id).collect())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    j: usize,
+    i: usize,
+    height: usize,
+    width: usize,
+}
+
+impl Rect {
+    fn center(&self) -> (usize, usize) {
+        (self.j + self.height / 2, self.i + self.width / 2)
+    }
+}
+
+/// splits the map until the parts are small, puts a room into each part and joins
+/// the rooms of both halves of every split with a corridor
+fn rooms(width: usize, height: usize, rng: &mut StdRng) -> Vec<Vec<bool>> {
+    let mut floor = vec![vec![false; width]; height];
+    // the outer row and column stay walls
+    let area = Rect {
+        j: 1,
+        i: 1,
+        height: height - 2,
+        width: width - 2,
+    };
+    split(area, &mut floor, rng);
+    floor
+}
+
+/// carves the rooms of `area` and returns one of them to join it with its sibling
+fn split(area: Rect, floor: &mut [Vec<bool>], rng: &mut StdRng) -> Rect {
+    // a part is split if both halves can still hold a room and a wall between them
+    let smallest = SMALLEST_ROOM * 2 + 1;
+    let split_rows =
+        area.height >= smallest && (area.height >= area.width || area.width < smallest);
+    let split_columns = area.width >= smallest && !split_rows;
+
+    if !split_rows && !split_columns
+        || (area.height < smallest * 2 && area.width < smallest * 2 && rng.gen_bool(0.3))
+    {
+        let height = rng.gen_range(SMALLEST_ROOM.min(area.height), area.height + 1);
+        let width = rng.gen_range(SMALLEST_ROOM.min(area.width), area.width + 1);
+        let room = Rect {
+            j: area.j + rng.gen_range(0, area.height - height + 1),
+            i: area.i + rng.gen_range(0, area.width - width + 1),
+            height,
+            width,
+        };
+        for row in floor.iter_mut().skip(room.j).take(room.height) {
+            for tile in row.iter_mut().skip(room.i).take(room.width) {
+                *tile = true;
+            }
+        }
+        return room;
+    }
+
+    let (first, second) = if split_rows {
+        let at = rng.gen_range(SMALLEST_ROOM, area.height - SMALLEST_ROOM);
+        (
+            Rect { height: at, ..area },
+            Rect {
+                j: area.j + at + 1,
+                height: area.height - at - 1,
+                ..area
+            },
+        )
+    } else {
+        let at = rng.gen_range(SMALLEST_ROOM, area.width - SMALLEST_ROOM);
+        (
+            Rect { width: at, ..area },
+            Rect {
+                i: area.i + at + 1,
+                width: area.width - at - 1,
+                ..area
+            },
+        )
+    };
+    let a = split(first, floor, rng);
+    let b = split(second, floor, rng);
+    corridor(a.center(), b.center(), floor, rng);
+    if rng.gen_bool(0.5) {
+        a
+    } else {
+        b
+    }
+}
+
+/// an L shaped corridor between two tiles
+fn corridor(from: (usize, usize), to: (usize, usize), floor: &mut [Vec<bool>], rng: &mut StdRng) {
+    let corner = if rng.gen_bool(0.5) {
+        (from.0, to.1)
+    } else {
+        (to.0, from.1)
+    };
+    for (a, b) in [(from, corner), (corner, to)] {
+        for row in &mut floor[a.0.min(b.0)..=a.0.max(b.0)] {
+            for tile in &mut row[a.1.min(b.1)..=a.1.max(b.1)] {
+                *tile = true;
+            }
+        }
+    }
+}
+
+/// random noise smoothed into caves, caves which are not connected to the largest one are filled
+fn caves(width: usize, height: usize, rng: &mut StdRng) -> Vec<Vec<bool>> {
+    let inside = |j: usize, i: usize| j > 0 && i > 0 && j + 1 < height && i + 1 < width;
+    let mut floor: Vec<Vec<bool>> = (0..height)
+        .map(|j| {
+            (0..width)
+                .map(|i| inside(j, i) && rng.gen_bool(0.55))
+                .collect()
+        })
+        .collect();
+
+    for _ in 0..5 {
+        let mut next = floor.clone();
+        for (j, row) in next.iter_mut().enumerate() {
+            for (i, tile) in row.iter_mut().enumerate() {
+                if !inside(j, i) {
+                    continue;
+                }
+                let walls = floor[j - 1..=j + 1]
+                    .iter()
+                    .flat_map(|row| &row[i - 1..=i + 1])
+                    .filter(|floor| !**floor)
+                    .count()
+                    - usize::from(!floor[j][i]);
+                *tile = walls < 5;
+            }
+        }
+        floor = next;
+    }
+
+    // keep the largest cave
+    let mut region = vec![vec![usize::MAX; width]; height];
+    let mut sizes = vec![];
+    for j in 0..height {
+        for i in 0..width {
+            if !floor[j][i] || region[j][i] != usize::MAX {
+                continue;
+            }
+            let id = sizes.len();
+            let mut size = 0;
+            let mut stack = vec![(j, i)];
+            region[j][i] = id;
+            while let Some((cj, ci)) = stack.pop() {
+                size += 1;
+                for (nj, ni) in [(cj - 1, ci), (cj + 1, ci), (cj, ci - 1), (cj, ci + 1)] {
+                    if floor[nj][ni] && region[nj][ni] == usize::MAX {
+                        region[nj][ni] = id;
+                        stack.push((nj, ni));
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+    }
+    let largest = (0..sizes.len()).max_by_key(|id| sizes[*id]);
+    for j in 0..height {
+        for i in 0..width {
+            floor[j][i] = floor[j][i] && Some(region[j][i]) == largest;
+        }
+    }
+    floor
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{MapBlockTypes, Pos};
+    use crate::map_gen::{generate_maps, Legend, MapDefinition};
+    use crate::reachability::Reachability;
+
+    fn definitions(yaml: &str) -> Vec<MapDefinition> {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_generated_maps_are_connected() {
+        for algorithm in ["rooms", "caves"] {
+            for seed in 0..20 {
+                let definitions = definitions(&format!(
+                    "- name: deep
+  generate:
+      algorithm: {}
+      width: 30
+      height: 20
+      seed: {}
+      items: [{{id: 0, weight: 3}}, {{id: 1}}]
+      item_count: 4
+      enemies: [{{id: 2}}]
+      enemy_count: 3
+      exits: [deep.entrance]",
+                    algorithm, seed
+                ));
+                let maps = generate_maps(&definitions, &Legend::new()).unwrap();
+                let map = &maps[0];
+                let teleport = map
+                    .iter()
+                    .flatten()
+                    .find_map(|block| match block {
+                        MapBlockTypes::TeleportTrigger(m, j, i) => Some((*m, *j, *i)),
+                        _ => None,
+                    })
+                    .unwrap();
+                let reachability = Reachability::from(
+                    &maps,
+                    &[],
+                    &[],
+                    0,
+                    Pos {
+                        i: teleport.2,
+                        j: teleport.1,
+                    },
+                );
+
+                let mut things = 0;
+                for (j, row) in map.iter().enumerate() {
+                    for (i, block) in row.iter().enumerate() {
+                        if *block != MapBlockTypes::NotWalkable {
+                            assert!(
+                                reachability.reached(0, j, i),
+                                "{} {} {} {}",
+                                algorithm,
+                                seed,
+                                j,
+                                i
+                            );
+                        }
+                        if let MapBlockTypes::ItemTrigger(_) | MapBlockTypes::EnemyTrigger(_) =
+                            block
+                        {
+                            things += 1;
+                        }
+                    }
+                }
+                assert_eq!(things, 7);
+                assert!(map[0]
+                    .iter()
+                    .all(|block| *block == MapBlockTypes::NotWalkable));
+            }
+        }
+    }
+
+    #[test]
+    fn test_same_seed_same_map() {
+        let yaml = "- generate: {algorithm: caves, width: 20, height: 12, seed: 7}
+- generate: {algorithm: caves, width: 20, height: 12, seed: 7}";
+        let first = generate_maps(&definitions(yaml), &Legend::new()).unwrap();
+        let second = generate_maps(&definitions(yaml), &Legend::new()).unwrap();
+
+        assert_eq!(first, second);
+        // the index of the map is part of the seed
+        assert_ne!(first[0], first[1]);
+    }
+
+    #[test]
+    fn test_generator_errors() {
+        let error = |yaml: &str| {
+            generate_maps(&definitions(yaml), &Legend::new())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            error("- generate: {algorithm: rooms, width: 4, height: 20}"),
+            "map 0: a generated map needs to be at least 5x5"
+        );
+        assert_eq!(
+            error("- generate: {algorithm: rooms, width: 5, height: 5, enemy_count: 2, enemies: [{id: 0, weight: 0}]}"),
+            "map 0: enemies: the pool needs at least one entry with a weight above 0"
+        );
+        assert_eq!(
+            error("- generate: {algorithm: rooms, width: 5, height: 5, item_count: 20, items: [{id: 0}]}"),
+            "map 0: 21 things do not fit onto the 9 free tiles of the map"
+        );
+    }
+}
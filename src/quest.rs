@@ -0,0 +1,278 @@
+use crate::events::Action;
+use crate::game::Game;
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Quest {
+    pub id: usize,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub objectives: Vec<Objective>,
+    /// applied once all objectives are done
+    #[serde(default)]
+    pub rewards: Vec<Action>,
+    /// hidden quests only show up in the journal once started by `!start_quest`
+    #[serde(default)]
+    pub hidden: bool,
+}
+
+/// written as yaml tags, e.g. `!collect_item 3` or `!talk_to 0`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// item_id in global_items
+    CollectItem(usize),
+    /// enemy_id in enemies
+    DefeatEnemy(usize),
+    /// index in maps
+    ReachMap(usize),
+    /// npc_id in npcs
+    TalkTo(usize),
+}
+
+/// things happening in the game which can complete objectives
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuestEvent {
+    ItemCollected(usize),
+    EnemyDefeated(usize),
+    MapReached(usize),
+    NpcTalked(usize),
+}
+
+/// progress of a started quest
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct QuestState {
+    /// index in Game::quests
+    pub quest: usize,
+    /// one entry for every objective of the quest
+    pub done: Vec<bool>,
+    pub completed: bool,
+}
+
+impl Objective {
+    fn is_done_by(&self, event: &QuestEvent) -> bool {
+        matches!(
+            (self, event),
+            (Objective::CollectItem(a), QuestEvent::ItemCollected(b))
+                | (Objective::DefeatEnemy(a), QuestEvent::EnemyDefeated(b))
+                | (Objective::ReachMap(a), QuestEvent::MapReached(b))
+                | (Objective::TalkTo(a), QuestEvent::NpcTalked(b))
+                if a == b
+        )
+    }
+}
+
+impl Game {
+    pub fn start_visible_quests(&mut self) {
+        let visible: Vec<usize> = self
+            .quests
+            .iter()
+            .filter(|quest| !quest.hidden)
+            .map(|quest| quest.id)
+            .collect();
+        for quest_id in visible {
+            self.start_quest(quest_id);
+        }
+    }
+
+    pub fn start_quest(&mut self, quest_id: usize) {
+        let quest = match self.quests.iter().position(|quest| quest.id == quest_id) {
+            Some(v) => v,
+            None => {
+                log::warn!("quest {} not found", quest_id);
+                return;
+            }
+        };
+        if self.quest_log.iter().any(|state| state.quest == quest) {
+            return;
+        }
+
+        let mut state = QuestState {
+            quest,
+            done: vec![false; self.quests[quest].objectives.len()],
+            completed: false,
+        };
+        // items carried before the quest started count as well
+        for (index, objective) in self.quests[quest].objectives.iter().enumerate() {
+            if let Objective::CollectItem(item_id) = objective {
+                state.done[index] = self.inventory.position_of(*item_id).is_some();
+            }
+        }
+        self.quest_log.push(state);
+        self.info_queue.queue(
+            "Quest".to_string(),
+            format!("New quest: {}", self.quests[quest].name),
+        );
+        self.check_quest_completion();
+    }
+
+    pub fn quest_event(&mut self, event: QuestEvent) {
+        for state in self.quest_log.iter_mut().filter(|state| !state.completed) {
+            for (index, objective) in self.quests[state.quest].objectives.iter().enumerate() {
+                if objective.is_done_by(&event) {
+                    state.done[index] = true;
+                }
+            }
+        }
+        self.check_quest_completion();
+    }
+
+    fn check_quest_completion(&mut self) {
+        // rewards can complete quests on their own, so look for the next finished quest
+        // after each one instead of collecting them up front
+        while let Some(index) = self
+            .quest_log
+            .iter()
+            .position(|state| !state.completed && state.done.iter().all(|done| *done))
+        {
+            self.quest_log[index].completed = true;
+            let quest = self.quests[self.quest_log[index].quest].clone();
+            self.info_queue.queue(
+                "Quest".to_string(),
+                format!("Quest completed: {}", quest.name),
+            );
+            for reward in &quest.rewards {
+                self.apply_action(reward);
+            }
+        }
+    }
+
+    pub fn objective_text(&self, objective: &Objective) -> String {
+        match objective {
+            Objective::CollectItem(item_id) => {
+                match self.inventory.get_item_props(item_id, &self.global_items) {
+                    Some(props) => format!("Collect a {}", props.name),
+                    None => format!("Collect item {}", item_id),
+                }
+            }
+            Objective::DefeatEnemy(enemy_id) => match self.entities.get(enemy_id + 1) {
+                Some(enemy) => format!("Defeat the {}", enemy.name),
+                None => format!("Defeat enemy {}", enemy_id),
+            },
+            Objective::ReachMap(map) => format!("Reach map {}", map),
+            Objective::TalkTo(npc_id) => match self.npcs.iter().find(|npc| npc.id == *npc_id) {
+                Some(npc) => format!("Talk to {}", npc.name),
+                None => format!("Talk to npc {}", npc_id),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, MapBlockTypes, Movement};
+    use crate::quest::{Objective, QuestEvent};
+
+    fn quest_game() -> Game {
+        Game::from_yaml(
+            "
+quests:
+    - id: 0
+      name: Troll hunt
+      objectives:
+          - !collect_item 0
+          - !defeat_enemy 0
+      rewards:
+          - !give_gold 50
+    - id: 1
+      name: Secret
+      hidden: true
+      objectives:
+          - !reach_map 1
+    - id: 5
+      name: Empty
+      hidden: true
+      objectives: []
+",
+        )
+    }
+
+    #[test]
+    fn test_parse_objectives() {
+        let game = quest_game();
+        assert_eq!(
+            game.quests[0].objectives,
+            vec![Objective::CollectItem(0), Objective::DefeatEnemy(0)]
+        );
+    }
+
+    #[test]
+    fn test_complete_quest() {
+        let mut game = quest_game();
+        assert_eq!(game.quest_log.len(), 1);
+
+        game.pick_up_item(0).unwrap();
+        assert!(!game.quest_log[0].completed);
+        game.quest_event(QuestEvent::EnemyDefeated(0));
+
+        assert!(game.quest_log[0].completed);
+        assert_eq!(game.gold, 50);
+    }
+
+    #[test]
+    fn test_hidden_quest() {
+        let mut game = quest_game();
+
+        game.quest_event(QuestEvent::MapReached(1));
+        game.start_quest(1);
+        assert!(!game.quest_log[1].completed);
+
+        game.quest_event(QuestEvent::MapReached(1));
+        assert!(game.quest_log[1].completed);
+    }
+
+    #[test]
+    fn test_unknown_quest() {
+        let mut game = quest_game();
+
+        game.start_quest(4);
+        game.start_quest(5);
+
+        assert_eq!(game.quest_log.len(), 2);
+        assert!(game.quest_log[1].completed);
+    }
+
+    #[test]
+    fn test_rewards_applied_once() {
+        let mut game = Game::from_yaml(
+            "
+quests:
+    - id: 0
+      name: Loot
+      objectives:
+          - !defeat_enemy 0
+      rewards:
+          - !give_item 1
+    - id: 1
+      name: Bounty
+      objectives:
+          - !defeat_enemy 0
+      rewards:
+          - !give_gold 20
+    - id: 2
+      name: Boots
+      objectives:
+          - !collect_item 1
+      rewards:
+          - !give_gold 5
+",
+        );
+
+        game.quest_event(QuestEvent::EnemyDefeated(0));
+
+        assert!(game.quest_log.iter().all(|state| state.completed));
+        assert_eq!(game.gold, 25);
+        assert!(game.inventory.position_of(1).is_some());
+    }
+
+    #[test]
+    fn test_collect_unknown_item() {
+        let mut game = Game::from_yaml("maps: ['|_|I9|_|']");
+
+        game.east();
+
+        assert!(game.inventory.inventory.is_empty());
+        assert_eq!(game.maps[0][0][1], MapBlockTypes::ItemTrigger(9));
+    }
+}
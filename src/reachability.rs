@@ -0,0 +1,214 @@
+use crate::edges::{edge_entry, Edges, Side};
+use crate::game::{MapBlockTypes, Pos};
+use crate::map_gen::Map;
+use std::collections::VecDeque;
+
+/// the tiles a player can get to by walking, crossing map edges, using teleports and
+/// going down the stairs into the listed levels of the dungeon.
+/// doors and conditional tiles are taken as open, traps, events and scripts
+/// which move the player are not followed
+pub struct Reachability {
+    /// tiles the player can stand on or interact with, by map, row and column
+    reached: Vec<Vec<Vec<bool>>>,
+}
+
+/// tiles which can be bumped into but not walked onto
+fn blocks_the_way(block: &MapBlockTypes) -> bool {
+    matches!(
+        block,
+        MapBlockTypes::MerchantTrigger(_)
+            | MapBlockTypes::Chest(_)
+            | MapBlockTypes::Npc(_)
+            | MapBlockTypes::Readable(_)
+            | MapBlockTypes::Shrine(_)
+            | MapBlockTypes::Lever(_, _)
+    )
+}
+
+impl Reachability {
+    /// `levels` are the maps of the dungeon by depth, starting at depth 1
+    pub fn from(
+        maps: &[Map],
+        edges: &[Edges],
+        levels: &[usize],
+        map: usize,
+        pos: Pos,
+    ) -> Reachability {
+        let grid = |value: bool| -> Vec<Vec<Vec<bool>>> {
+            maps.iter()
+                .map(|map| map.iter().map(|row| vec![value; row.len()]).collect())
+                .collect()
+        };
+        let mut reachability = Reachability {
+            reached: grid(false),
+        };
+        let mut stood_on = grid(false);
+        let tile = |map: usize, j: usize, i: usize| maps.get(map)?.get(j)?.get(i);
+
+        let mut queue = VecDeque::new();
+        if has_tile(maps, map, pos.j, pos.i) {
+            queue.push_back((map, pos));
+        }
+        while let Some((m, pos)) = queue.pop_front() {
+            if stood_on[m][pos.j][pos.i] {
+                continue;
+            }
+            stood_on[m][pos.j][pos.i] = true;
+            reachability.reached[m][pos.j][pos.i] = true;
+
+            let rows = maps[m].len();
+            let columns = maps[m][pos.j].len();
+            let mut next = vec![];
+            if pos.j > 0 {
+                next.push(Pos {
+                    i: pos.i,
+                    j: pos.j - 1,
+                });
+            }
+            if pos.j + 1 < rows {
+                next.push(Pos {
+                    i: pos.i,
+                    j: pos.j + 1,
+                });
+            }
+            if pos.i > 0 {
+                next.push(Pos {
+                    i: pos.i - 1,
+                    j: pos.j,
+                });
+            }
+            if pos.i + 1 < columns {
+                next.push(Pos {
+                    i: pos.i + 1,
+                    j: pos.j,
+                });
+            }
+            for pos in next {
+                let block = match tile(m, pos.j, pos.i) {
+                    Some(MapBlockTypes::NotWalkable) | None => continue,
+                    Some(v) => v,
+                };
+                reachability.reached[m][pos.j][pos.i] = true;
+                match block {
+                    &MapBlockTypes::TeleportTrigger(map, j, i) => {
+                        if has_tile(maps, map, j, i) {
+                            queue.push_back((map, Pos { i, j }));
+                        }
+                    }
+                    block if blocks_the_way(block) => {}
+                    _ => queue.push_back((m, pos)),
+                }
+            }
+
+            // stairs down on a level lead to the next one, anywhere else to the first one
+            if maps[m][pos.j][pos.i] == MapBlockTypes::StairsDown {
+                let depth = levels
+                    .iter()
+                    .position(|level| *level == m)
+                    .map_or(0, |d| d + 1);
+                if let Some(level) = levels.get(depth) {
+                    queue.extend(
+                        crate::depth::stairs_up(&maps[*level])
+                            .into_iter()
+                            .map(|pos| (*level, pos)),
+                    );
+                }
+            }
+
+            // edges of the map come first, new map triggers enter the other map
+            // on the opposite side the same way Game does it
+            for side in Side::ALL {
+                if let Some(entry) = edge_entry(maps, edges, m, &pos, side) {
+                    queue.push_back(entry);
+                    continue;
+                }
+                let new_map = match maps[m][pos.j][pos.i] {
+                    MapBlockTypes::NewMapTrigger(new_map) => new_map,
+                    _ => continue,
+                };
+                let entry = match side {
+                    Side::North if pos.j == 0 => Pos {
+                        i: pos.i,
+                        j: rows - 1,
+                    },
+                    Side::South if pos.j == rows - 1 => Pos { i: pos.i, j: 0 },
+                    Side::West if pos.i == 0 => Pos {
+                        i: columns - 1,
+                        j: pos.j,
+                    },
+                    Side::East if pos.i == columns - 1 => Pos { i: 0, j: pos.j },
+                    _ => continue,
+                };
+                if has_tile(maps, new_map, entry.j, entry.i) {
+                    queue.push_back((new_map, entry));
+                }
+            }
+        }
+        reachability
+    }
+
+    pub fn reached(&self, map: usize, j: usize, i: usize) -> bool {
+        self.reached
+            .get(map)
+            .and_then(|map| map.get(j))
+            .and_then(|row| row.get(i))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    pub fn map_reached(&self, map: usize) -> bool {
+        self.reached
+            .get(map)
+            .is_some_and(|map| map.iter().flatten().any(|reached| *reached))
+    }
+}
+
+/// whether the map has a tile at j and i
+fn has_tile(maps: &[Map], map: usize, j: usize, i: usize) -> bool {
+    maps.get(map)
+        .and_then(|map| map.get(j))
+        .and_then(|row| row.get(i))
+        .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{MapBlockTypes, Pos};
+    use crate::map_gen::generate_map;
+    use crate::reachability::Reachability;
+
+    #[test]
+    fn test_reach_test_scenario() {
+        let game = crate::game::Game::new();
+        let reachability =
+            Reachability::from(&game.maps, &game.edges, &game.levels, 0, game.pos.clone());
+
+        for (m, map) in game.maps.iter().enumerate() {
+            assert!(reachability.map_reached(m));
+            for (j, row) in map.iter().enumerate() {
+                for (i, block) in row.iter().enumerate() {
+                    if let MapBlockTypes::ItemTrigger(_) | MapBlockTypes::EnemyTrigger(_) = block {
+                        assert!(reachability.reached(m, j, i));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_edges_and_teleports() {
+        let maps = vec![
+            generate_map(0, "|_|M1|_|\\n|x|x|x|\\n|I0|x|_|".to_string()).unwrap(),
+            generate_map(1, "|x|T(0,2,0)|x|\\n|x|_|N0|\\n|x|_|_|".to_string()).unwrap(),
+        ];
+        let reachability = Reachability::from(&maps, &[], &[], 0, Pos { i: 0, j: 0 });
+
+        // entered at the bottom of map 1 below M1
+        assert!(reachability.reached(1, 2, 1));
+        assert!(reachability.reached(1, 1, 2));
+        // the teleport drops the player onto the item
+        assert!(reachability.reached(0, 2, 0));
+        assert!(!reachability.reached(0, 2, 2));
+        assert!(!reachability.reached(1, 0, 0));
+    }
+}
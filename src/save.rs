@@ -0,0 +1,601 @@
+use crate::custom_layer::RpProperties;
+use crate::game::{Entity, Game, Inventory, Pos};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// version of the save format written by this build, bump it together with a migration
+pub const SAVE_VERSION: u32 = 3;
+pub const SAVE_SLOTS: usize = 3;
+/// number of autosaves kept, older ones are deleted
+pub const AUTOSAVE_HISTORY: usize = 5;
+
+/// everything about a running game which is not read from the scenario file
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SaveGame {
+    pub version: u32,
+    /// version of the game which wrote the save
+    pub engine_version: String,
+    /// content hash of the scenario file the save was made with, 0 if unknown
+    pub scenario_hash: u64,
+    pub saved_at: chrono::DateTime<chrono::Local>,
+    pub playername: String,
+    pub cur_map: usize,
+    pub pos: Pos,
+    pub maps: Vec<crate::map_gen::Map>,
+    pub inventory: Inventory,
+    pub gold: usize,
+    pub entities: Vec<Entity>,
+    pub max_hp: i32,
+    pub rp_properties: RpProperties,
+    pub status_effects: Vec<crate::status::StatusEffect>,
+    pub flags: crate::flags::Flags,
+    pub fight: Option<crate::fight::Fight>,
+    pub turn: usize,
+    pub merchants: Vec<crate::merchant::Merchant>,
+    pub chests: Vec<crate::chest::ChestProps>,
+    pub shrine_states: Vec<crate::shrine::ShrineState>,
+    pub quest_log: Vec<crate::quest::QuestState>,
+    pub fired_events: Vec<usize>,
+    pub read_readables: Vec<usize>,
+    /// maps of the levels of the dungeon, none in saves which do not know about them
+    pub levels: Option<Vec<usize>>,
+    pub descents: Vec<crate::depth::Descent>,
+}
+
+/// state of the open save or load screen
+#[derive(Debug, Clone)]
+pub struct SlotMenu {
+    pub selected: usize,
+    pub saving: bool,
+    pub entries: Vec<SlotEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SlotEntry {
+    /// line describing the entry in the menu
+    pub label: String,
+    /// file of the save, the entry starting a new game has none
+    pub path: Option<PathBuf>,
+}
+
+/// directory the save slots are stored in, following the platform conventions for user data
+pub fn saves_dir() -> PathBuf {
+    let data_dir = std::env::var_os("XDG_DATA_HOME")
+        .or_else(|| std::env::var_os("APPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    data_dir.join("easy-dungeon-scrawl").join("saves")
+}
+
+/// slots are numbered starting with 1
+pub fn slot_path(slot: usize) -> PathBuf {
+    saves_dir().join(format!("slot_{}.yaml", slot))
+}
+
+/// autosaves are numbered starting with 0 for the newest one
+pub fn autosave_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("autosave_{}.yaml", index))
+}
+
+/// `MIGRATIONS[n]` turns a save of version n + 1 into one of version n + 2
+const MIGRATIONS: [fn(&mut Mapping); (SAVE_VERSION - 1) as usize] = [migrate_v1, migrate_v2];
+
+/// saves from before versioning do not know the engine and scenario they were made with
+fn migrate_v1(save: &mut Mapping) {
+    save.insert("engine_version".into(), "unknown".into());
+    save.insert("scenario_hash".into(), 0.into());
+}
+
+/// saves from before the dungeon had depth, the levels of the scenario are kept when loading
+fn migrate_v2(save: &mut Mapping) {
+    save.insert("levels".into(), Value::Null);
+    save.insert("descents".into(), Value::Sequence(vec![]));
+}
+
+pub fn read_save(path: &Path) -> Result<SaveGame, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("can not read {}: {}", path.display(), e))?;
+    parse_save(&contents).map_err(|e| format!("{} is broken: {}", path.display(), e))
+}
+
+/// parses a save of any known version and migrates it to the current one
+pub fn parse_save(contents: &str) -> Result<SaveGame, String> {
+    let mut value: Value = serde_yaml::from_str(contents).map_err(|e| e.to_string())?;
+    let save = value
+        .as_mapping_mut()
+        .ok_or("the save is not a mapping".to_string())?;
+
+    let version = save.get("version").and_then(Value::as_u64).unwrap_or(1) as u32;
+    if version == 0 {
+        return Err("it has no valid save version".to_string());
+    }
+    if version > SAVE_VERSION {
+        return Err(format!(
+            "it was written by a newer version of the game (save version {})",
+            version
+        ));
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(save);
+    }
+    save.insert("version".into(), SAVE_VERSION.into());
+
+    serde_yaml::from_value(value).map_err(|e| e.to_string())
+}
+
+pub fn has_saves() -> bool {
+    (1..=SAVE_SLOTS).any(|slot| slot_path(slot).exists()) || autosave_path(&saves_dir(), 0).exists()
+}
+
+/// writes to a temporary file first, so a crash while saving keeps the old save intact
+pub fn write_atomic(path: &Path, contents: &str) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("can not create {}: {}", dir.display(), e))?;
+    }
+    let tmp_path = path.with_extension("tmp");
+    let write = |tmp_path: &Path| -> std::io::Result<()> {
+        let mut file = std::fs::File::create(tmp_path)?;
+        file.write_all(contents.as_bytes())?;
+        // the contents have to be on the disk before the rename makes them the save
+        file.sync_all()
+    };
+    write(&tmp_path).map_err(|e| format!("can not write {}: {}", tmp_path.display(), e))?;
+    std::fs::rename(&tmp_path, path).map_err(|e| format!("can not write {}: {}", path.display(), e))
+}
+
+fn save_entry(name: String, path: PathBuf, scenario_hash: u64) -> SlotEntry {
+    let label = if !path.exists() {
+        format!("{} - empty", name)
+    } else {
+        match read_save(&path) {
+            Ok(save) => format!(
+                "{} - {} - map {} - turn {} - {}{}",
+                name,
+                save.playername,
+                save.cur_map,
+                save.turn,
+                save.saved_at.format("%Y-%m-%d %H:%M"),
+                if save.scenario_hash != 0 && save.scenario_hash != scenario_hash {
+                    " - other scenario"
+                } else {
+                    ""
+                }
+            ),
+            Err(_) => format!("{} - unreadable", name),
+        }
+    };
+    SlotEntry {
+        label,
+        path: Some(path),
+    }
+}
+
+impl Game {
+    pub fn to_save(&self) -> SaveGame {
+        SaveGame {
+            version: SAVE_VERSION,
+            engine_version: env!("CARGO_PKG_VERSION").to_string(),
+            scenario_hash: self.scenario_hash,
+            saved_at: chrono::Local::now(),
+            playername: self.playername.clone(),
+            cur_map: self.cur_map,
+            pos: self.pos.clone(),
+            maps: self.maps.clone(),
+            inventory: self.inventory.clone(),
+            gold: self.gold,
+            entities: self.entities.clone(),
+            max_hp: self.max_hp,
+            rp_properties: self.rp_properties.clone(),
+            status_effects: self.status_effects.clone(),
+            flags: self.flags.clone(),
+            fight: self.fight.clone(),
+            turn: self.turn,
+            merchants: self.merchants.clone(),
+            chests: self.chests.clone(),
+            shrine_states: self.shrine_states.clone(),
+            quest_log: self.quest_log.clone(),
+            fired_events: self.fired_events.clone(),
+            read_readables: self.read_readables.clone(),
+            levels: Some(self.levels.clone()),
+            descents: self.descents.clone(),
+        }
+    }
+
+    pub fn apply_save(&mut self, save: SaveGame) {
+        self.playername = save.playername;
+        self.cur_map = save.cur_map;
+        self.pos = save.pos;
+        self.maps = save.maps;
+        self.inventory = save.inventory;
+        self.gold = save.gold;
+        self.entities = save.entities;
+        self.max_hp = save.max_hp;
+        self.rp_properties = save.rp_properties;
+        self.status_effects = save.status_effects;
+        self.flags = save.flags;
+        self.turn = save.turn;
+        self.merchants = save.merchants;
+        self.chests = save.chests;
+        self.shrine_states = save.shrine_states;
+        self.quest_log = save.quest_log;
+        self.fired_events = save.fired_events;
+        self.read_readables = save.read_readables;
+        if let Some(levels) = save.levels {
+            self.levels = levels;
+        }
+        self.descents = save.descents;
+        // generated levels of the dungeon are not part of the scenario and have no edges
+        self.edges
+            .resize(self.maps.len(), crate::edges::Edges::new());
+
+        self.trade = None;
+        self.loot = None;
+        self.dialogue = None;
+        self.reader = None;
+        self.slot_menu = None;
+        self.active_menu_item = if save.fight.is_some() {
+            crate::MenuItem::Fight
+        } else {
+            crate::MenuItem::Game
+        };
+        self.fight = save.fight;
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        let contents = serde_yaml::to_string(&self.to_save()).map_err(|e| e.to_string())?;
+        write_atomic(path, &contents)
+    }
+
+    /// called at map transitions and before fights
+    pub fn autosave(&mut self) {
+        if !self.autosave {
+            return;
+        }
+        match self.autosave_to(&saves_dir()) {
+            Ok(()) => log::info!("autosaved at turn {}", self.turn),
+            Err(e) => log::warn!("autosave failed: {}", e),
+        }
+    }
+
+    /// moves the existing autosaves in `dir` one place back and writes the newest one
+    pub fn autosave_to(&self, dir: &Path) -> Result<(), String> {
+        // the old autosaves are only rotated once the new one is written
+        let new_path = autosave_path(dir, 0).with_extension("new");
+        self.save_to(&new_path)?;
+        for index in (1..AUTOSAVE_HISTORY).rev() {
+            let older = autosave_path(dir, index - 1);
+            if older.exists() {
+                std::fs::rename(&older, autosave_path(dir, index))
+                    .map_err(|e| format!("can not rotate {}: {}", older.display(), e))?;
+            }
+        }
+        std::fs::rename(&new_path, autosave_path(dir, 0))
+            .map_err(|e| format!("can not write {}: {}", new_path.display(), e))
+    }
+
+    pub fn load_from(&mut self, path: &Path) -> Result<(), String> {
+        let save = read_save(path)?;
+        if save.scenario_hash != 0 && save.scenario_hash != self.scenario_hash {
+            log::warn!(
+                "{} was made with another scenario file (engine {})",
+                path.display(),
+                save.engine_version
+            );
+            self.info_queue.queue(
+                "Warning".to_string(),
+                "This save was made with a different scenario file, things may be missing or out of place".to_string(),
+            );
+        }
+        self.apply_save(save);
+        Ok(())
+    }
+
+    pub fn save_slot(&mut self, slot: usize) {
+        match self.save_to(&slot_path(slot)) {
+            Ok(()) => self
+                .info_queue
+                .queue("Saved".to_string(), format!("Game saved to slot {}", slot)),
+            Err(e) => {
+                log::warn!("{}", e);
+                self.info_queue.queue("Save failed".to_string(), e);
+            }
+        }
+    }
+
+    pub fn load_save(&mut self, path: &Path) {
+        match self.load_from(path) {
+            Ok(()) => self
+                .info_queue
+                .queue("Loaded".to_string(), "Game loaded".to_string()),
+            Err(e) => {
+                log::warn!("{}", e);
+                self.info_queue.queue("Load failed".to_string(), e);
+            }
+        }
+    }
+
+    pub fn open_slot_menu(&mut self, saving: bool) {
+        let mut entries: Vec<SlotEntry> = (1..=SAVE_SLOTS)
+            .map(|slot| {
+                save_entry(
+                    format!("Slot {}", slot),
+                    slot_path(slot),
+                    self.scenario_hash,
+                )
+            })
+            .collect();
+        if !saving {
+            entries.insert(
+                0,
+                SlotEntry {
+                    label: "New game".to_string(),
+                    path: None,
+                },
+            );
+            let dir = saves_dir();
+            for index in 0..AUTOSAVE_HISTORY {
+                let path = autosave_path(&dir, index);
+                if path.exists() {
+                    entries.push(save_entry(
+                        format!("Autosave {}", index + 1),
+                        path,
+                        self.scenario_hash,
+                    ));
+                }
+            }
+        }
+        self.slot_menu = Some(SlotMenu {
+            selected: 0,
+            saving,
+            entries,
+        });
+        self.active_menu_item = crate::MenuItem::Saves;
+    }
+
+    pub fn close_slot_menu(&mut self) {
+        // there is no game to go back to after a defeat
+        if self.is_defeated() {
+            return;
+        }
+        self.slot_menu = None;
+        self.active_menu_item = crate::MenuItem::Game;
+    }
+
+    pub fn slot_menu_up(&mut self) {
+        if let Some(menu) = self.slot_menu.as_mut() {
+            menu.selected = menu.selected.saturating_sub(1);
+        }
+    }
+
+    pub fn slot_menu_down(&mut self) {
+        if let Some(menu) = self.slot_menu.as_mut() {
+            if menu.selected + 1 < menu.entries.len() {
+                menu.selected += 1;
+            }
+        }
+    }
+
+    pub fn slot_menu_confirm(&mut self) {
+        let menu = match self.slot_menu.clone() {
+            Some(v) => v,
+            None => return,
+        };
+        let entry = menu.entries[menu.selected].clone();
+        match entry.path {
+            Some(_) if menu.saving => {
+                self.save_slot(menu.selected + 1);
+                self.close_slot_menu();
+            }
+            Some(path) if !path.exists() => self
+                .info_queue
+                .queue("Load".to_string(), "Nothing saved there yet".to_string()),
+            Some(path) => self.load_save(&path),
+            // the entry without a file starts over
+            None => {
+                let (autosave, seed) = (self.autosave, self.seed);
+                match Game::with_scenario(&self.scenario_path) {
+                    Ok(game) => *self = game,
+                    Err(e) => {
+                        log::warn!("can not start a new game: {}", e);
+                        self.info_queue.queue("New game".to_string(), e.to_string());
+                        return;
+                    }
+                }
+                self.autosave = autosave;
+                self.set_seed(seed);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, MapBlockTypes, Pos};
+    use crate::save::{autosave_path, parse_save, read_save, AUTOSAVE_HISTORY, SAVE_VERSION};
+    use crate::MenuItem;
+    use std::path::PathBuf;
+
+    /// a directory of its own for every test, removed again when the test ends
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(test: &str) -> TempDir {
+            TempDir(std::env::temp_dir().join(format!(
+                "easy-dungeon-scrawl-{}-{}",
+                test,
+                std::process::id()
+            )))
+        }
+
+        fn join(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new("save_and_load");
+        let path = dir.join("save.yaml");
+        let mut game = Game::from_yaml("");
+        game.cur_map = 1;
+        game.pos = Pos { i: 4, j: 5 };
+        game.maps[1][2][2] = MapBlockTypes::Door(0, true);
+        game.pick_up_item(0).unwrap();
+        game.entities[1].hp = 3;
+        game.set_flag("saved".to_string());
+        game.start_encounter(0);
+        game.save_to(&path).unwrap();
+
+        let mut loaded = Game::from_yaml("");
+        loaded.load_from(&path).unwrap();
+
+        assert_eq!(loaded.cur_map, 1);
+        assert_eq!((loaded.pos.i, loaded.pos.j), (4, 5));
+        assert_eq!(loaded.maps[1][2][2], MapBlockTypes::Door(0, true));
+        assert_eq!(loaded.inventory.inventory.len(), 1);
+        assert_eq!(loaded.entities[1].hp, 3);
+        assert!(loaded.flags.is_set("saved"));
+        assert!(loaded.fight.is_some());
+        assert_eq!(loaded.active_menu_item, MenuItem::Fight);
+    }
+
+    #[test]
+    fn test_autosave_history() {
+        let temp = TempDir::new("autosave_history");
+        let dir = temp.join("autosaves");
+        let mut game = Game::from_yaml("");
+        for turn in 0..AUTOSAVE_HISTORY + 2 {
+            game.turn = turn;
+            game.autosave_to(&dir).unwrap();
+        }
+
+        assert_eq!(
+            read_save(&autosave_path(&dir, 0)).unwrap().turn,
+            AUTOSAVE_HISTORY + 1
+        );
+        assert_eq!(
+            read_save(&autosave_path(&dir, 1)).unwrap().turn,
+            AUTOSAVE_HISTORY
+        );
+        assert!(autosave_path(&dir, AUTOSAVE_HISTORY - 1).exists());
+        assert!(!autosave_path(&dir, AUTOSAVE_HISTORY).exists());
+        assert!(!autosave_path(&dir, 0).with_extension("tmp").exists());
+        assert!(!autosave_path(&dir, 0).with_extension("new").exists());
+    }
+
+    #[test]
+    fn test_failed_autosave_keeps_history() {
+        let temp = TempDir::new("failed_autosave");
+        let dir = temp.join("autosaves");
+        let mut game = Game::from_yaml("");
+        for turn in 0..2 {
+            game.turn = turn;
+            game.autosave_to(&dir).unwrap();
+        }
+
+        // a directory in the way of the new autosave makes writing it fail
+        std::fs::create_dir_all(autosave_path(&dir, 0).with_extension("new")).unwrap();
+        game.turn = 2;
+        assert!(game.autosave_to(&dir).is_err());
+
+        assert_eq!(read_save(&autosave_path(&dir, 0)).unwrap().turn, 1);
+        assert_eq!(read_save(&autosave_path(&dir, 1)).unwrap().turn, 0);
+    }
+
+    #[test]
+    fn test_migrate_unversioned_save() {
+        let game = Game::from_yaml("");
+        let mut save = serde_yaml::to_value(game.to_save()).unwrap();
+        let mapping = save.as_mapping_mut().unwrap();
+        for key in ["version", "engine_version", "scenario_hash"] {
+            mapping.remove(key);
+        }
+
+        let migrated = parse_save(&serde_yaml::to_string(&save).unwrap()).unwrap();
+
+        assert_eq!(migrated.version, SAVE_VERSION);
+        assert_eq!(migrated.scenario_hash, 0);
+        // the levels of the scenario are kept when loading a save from before the dungeon
+        assert_eq!(migrated.levels, None);
+        assert!(migrated.descents.is_empty());
+        assert_eq!(migrated.playername, game.playername);
+    }
+
+    #[test]
+    fn test_reject_newer_save() {
+        let mut save = Game::from_yaml("").to_save();
+        save.version = SAVE_VERSION + 1;
+
+        assert!(parse_save(&serde_yaml::to_string(&save).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_warn_about_other_scenario() {
+        let dir = TempDir::new("other_scenario");
+        let path = dir.join("save.yaml");
+        let mut game = Game::from_yaml("");
+        game.scenario_hash += 1;
+        game.save_to(&path).unwrap();
+
+        let mut loaded = Game::from_yaml("");
+        loaded.info_queue.queue.clear();
+        loaded.load_from(&path).unwrap();
+
+        assert_eq!(loaded.info_queue.head().unwrap().title, "Warning");
+    }
+
+    #[test]
+    fn test_load_missing_save() {
+        let dir = TempDir::new("missing_save");
+        let mut game = Game::from_yaml("");
+        let result = game.load_from(&dir.join("missing.yaml"));
+
+        assert!(result.is_err());
+        assert_eq!(game.turn, 0);
+    }
+
+    #[test]
+    fn test_defeat_ends_the_game() {
+        let mut game = Game::from_yaml("");
+        game.start_encounter(0);
+        game.fight.as_mut().unwrap().entities[0].hp = 0;
+        game.fight_round();
+
+        assert!(game.fight.is_none());
+        assert_eq!(game.active_menu_item, MenuItem::Saves);
+        assert_eq!(
+            game.slot_menu.as_ref().unwrap().entries[0].label,
+            "New game"
+        );
+
+        // the load menu can not be left while defeated
+        game.close_slot_menu();
+        assert_eq!(game.active_menu_item, MenuItem::Saves);
+    }
+
+    #[test]
+    fn test_new_game_keeps_the_seed() {
+        use rand::Rng;
+
+        let mut game = Game::from_yaml("");
+        game.scenario_path = crate::game::DEFAULT_SCENARIO.to_string();
+        game.set_seed(Some(42));
+        let roll: u64 = game.rng.gen();
+
+        game.open_slot_menu(false);
+        game.slot_menu_confirm();
+
+        assert_eq!(game.seed, Some(42));
+        assert_eq!(game.rng.gen::<u64>(), roll);
+    }
+}
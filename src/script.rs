@@ -0,0 +1,872 @@
+//! small lua like scripting language for scenario logic
+//!
+//! ```text
+//! -- comments start with two dashes
+//! let count = counter("visits") + 1
+//! if count >= 3 and not flag("blessed") then
+//!     message("The statue nods")
+//!     set_flag("blessed")
+//! elseif count == 1 then
+//!     message("The statue looks at you")
+//! end
+//! add_counter("visits", 1)
+//! ```
+//!
+//! Scripts only reach the game through the functions in `Interpreter::call`
+//! and stop with an error after `MAX_STEPS` statements or loop iterations.
+//! Blocks, calls, parentheses and chains of operators may be nested at most
+//! `MAX_NESTING` deep.
+
+use crate::game::{Game, Pos};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+pub const MAX_STEPS: usize = 10_000;
+/// keeps the recursive parser and interpreter from running out of stack
+pub const MAX_NESTING: usize = 64;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Script {
+    pub id: usize,
+    /// path of a file containing the script, relative to the scenario file
+    pub file: Option<String>,
+    /// the script itself, filled from `file` when the scenario is loaded
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    Number(i64),
+    Str(String),
+}
+
+impl Value {
+    fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    fn type_name(&self) -> &str {
+        match self {
+            Value::Nil => "nil",
+            Value::Bool(_) => "boolean",
+            Value::Number(_) => "number",
+            Value::Str(_) => "string",
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Nil => write!(f, "nil"),
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Number(v) => write!(f, "{}", v),
+            Value::Str(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(i64),
+    Str(String),
+    Ident(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: [&str; 17] = [
+    "==", "~=", "!=", "<=", ">=", "..", "<", ">", "=", "+", "-", "*", "/", "%", "(", ")", ",",
+];
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = vec![];
+    for (line_index, line) in source.lines().enumerate() {
+        let line_nr = line_index + 1;
+        let chars: Vec<char> = line.chars().collect();
+        let mut pos = 0;
+        while pos < chars.len() {
+            let c = chars[pos];
+            // symbols and comment starts are at most two characters long
+            let rest: String = chars[pos..].iter().take(2).collect();
+            if c.is_whitespace() {
+                pos += 1;
+            } else if rest.starts_with("--") {
+                break;
+            } else if c.is_ascii_digit() {
+                let digits: String = chars[pos..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_digit())
+                    .collect();
+                pos += digits.len();
+                let number = digits
+                    .parse()
+                    .map_err(|_| format!("line {}: number {} is too large", line_nr, digits))?;
+                tokens.push((Token::Number(number), line_nr));
+            } else if c.is_alphabetic() || c == '_' {
+                let ident: String = chars[pos..]
+                    .iter()
+                    .take_while(|c| c.is_alphanumeric() || **c == '_')
+                    .collect();
+                pos += ident.chars().count();
+                tokens.push((Token::Ident(ident), line_nr));
+            } else if c == '"' || c == '\'' {
+                let text: String = chars[pos + 1..].iter().take_while(|x| **x != c).collect();
+                pos += text.chars().count() + 1;
+                if pos >= chars.len() {
+                    return Err(format!("line {}: unterminated string", line_nr));
+                }
+                pos += 1;
+                tokens.push((Token::Str(text), line_nr));
+            } else if let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+                pos += symbol.len();
+                tokens.push((Token::Symbol(symbol), line_nr));
+            } else {
+                return Err(format!("line {}: unexpected character '{}'", line_nr, c));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Value(Value),
+    Var(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+    Call(String, Vec<Expr>, usize),
+}
+
+#[derive(Debug, Clone)]
+enum Stmt {
+    Assign(String, Expr),
+    /// branches with their condition, followed by the optional else branch
+    If(Vec<(Expr, Vec<Stmt>)>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Call(Expr),
+}
+
+const KEYWORDS: [&str; 15] = [
+    "let", "local", "if", "then", "elseif", "else", "end", "while", "do", "and", "or", "not",
+    "nil", "true", "false",
+];
+
+/// binary operators ordered by binding strength, weakest first
+const PRECEDENCE: [&[&str]; 6] = [
+    &["or"],
+    &["and"],
+    &["==", "~=", "!=", "<", "<=", ">", ">="],
+    &[".."],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// how many blocks, calls and parentheses the parser is inside of
+    depth: usize,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(0, |(_, line)| *line)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(ident)) if ident == word)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), String> {
+        if !self.is_word(word) {
+            return Err(format!("line {}: expected '{}'", self.line(), word));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if !self.is_symbol(symbol) {
+            return Err(format!("line {}: expected '{}'", self.line(), symbol));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Ident(ident)) if !KEYWORDS.contains(&ident.as_str()) => {
+                let ident = ident.clone();
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => Err(format!("line {}: expected a name", self.line())),
+        }
+    }
+
+    /// goes one level deeper, failing once scripts are nested too deep
+    fn descend(&mut self) -> Result<(), String> {
+        if self.depth >= MAX_NESTING {
+            return Err(format!(
+                "line {}: nested more than {} levels deep",
+                self.line(),
+                MAX_NESTING
+            ));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    /// runs `parse` one level deeper
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.descend()?;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// parses statements until one of the given words or the end of the script
+    fn block(&mut self, until: &[&str]) -> Result<Vec<Stmt>, String> {
+        self.nested(|parser| parser.statements(until))
+    }
+
+    fn statements(&mut self, until: &[&str]) -> Result<Vec<Stmt>, String> {
+        let mut stmts = vec![];
+        while self.peek().is_some() && !until.iter().any(|word| self.is_word(word)) {
+            stmts.push(self.statement()?);
+        }
+        if !until.is_empty() && self.peek().is_none() {
+            return Err(format!("line {}: expected '{}'", self.line(), until[0]));
+        }
+        Ok(stmts)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, String> {
+        if self.is_word("let") || self.is_word("local") {
+            self.pos += 1;
+            let name = self.ident()?;
+            self.expect_symbol("=")?;
+            return Ok(Stmt::Assign(name, self.expr(0)?));
+        }
+        if self.is_word("if") {
+            self.pos += 1;
+            let mut branches = vec![];
+            let mut otherwise = vec![];
+            loop {
+                let condition = self.expr(0)?;
+                self.expect_word("then")?;
+                let body = self.block(&["end", "elseif", "else"])?;
+                branches.push((condition, body));
+                if self.is_word("elseif") {
+                    self.pos += 1;
+                    continue;
+                }
+                if self.is_word("else") {
+                    self.pos += 1;
+                    otherwise = self.block(&["end"])?;
+                }
+                self.expect_word("end")?;
+                return Ok(Stmt::If(branches, otherwise));
+            }
+        }
+        if self.is_word("while") {
+            self.pos += 1;
+            let condition = self.expr(0)?;
+            self.expect_word("do")?;
+            let body = self.block(&["end"])?;
+            self.expect_word("end")?;
+            return Ok(Stmt::While(condition, body));
+        }
+
+        let line = self.line();
+        let name = self.ident()?;
+        if self.is_symbol("=") {
+            self.pos += 1;
+            return Ok(Stmt::Assign(name, self.expr(0)?));
+        }
+        if self.is_symbol("(") {
+            return Ok(Stmt::Call(self.call(name, line)?));
+        }
+        Err(format!("line {}: expected '=' or '(' after {}", line, name))
+    }
+
+    fn call(&mut self, name: String, line: usize) -> Result<Expr, String> {
+        self.expect_symbol("(")?;
+        let args = self.nested(|parser| {
+            let mut args = vec![];
+            while !parser.is_symbol(")") {
+                args.push(parser.expr(0)?);
+                if !parser.is_symbol(")") {
+                    parser.expect_symbol(",")?;
+                }
+            }
+            Ok(args)
+        })?;
+        self.expect_symbol(")")?;
+        Ok(Expr::Call(name, args, line))
+    }
+
+    fn binary_op(&self, level: usize) -> Option<&'static str> {
+        PRECEDENCE[level]
+            .iter()
+            .copied()
+            .find(|op| match self.peek() {
+                Some(Token::Symbol(symbol)) => symbol == op,
+                Some(Token::Ident(ident)) => ident == op,
+                _ => false,
+            })
+    }
+
+    fn expr(&mut self, level: usize) -> Result<Expr, String> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let depth = self.depth;
+        let result = self.binary_chain(level);
+        self.depth = depth;
+        result
+    }
+
+    /// operands joined by the operators of one level, every operator nests the chain one level deeper
+    fn binary_chain(&mut self, level: usize) -> Result<Expr, String> {
+        let mut left = self.expr(level + 1)?;
+        while let Some(op) = self.binary_op(level) {
+            self.pos += 1;
+            self.descend()?;
+            let right = self.expr(level + 1)?;
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.is_word("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.nested(Parser::unary)?)));
+        }
+        if self.is_symbol("-") {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.nested(Parser::unary)?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        let line = self.line();
+        let token = self.peek().cloned();
+        match token {
+            Some(Token::Number(number)) => {
+                self.pos += 1;
+                Ok(Expr::Value(Value::Number(number)))
+            }
+            Some(Token::Str(text)) => {
+                self.pos += 1;
+                Ok(Expr::Value(Value::Str(text)))
+            }
+            Some(Token::Symbol("(")) => {
+                self.pos += 1;
+                let expr = self.nested(|parser| parser.expr(0))?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            Some(Token::Ident(ident)) if ident == "true" || ident == "false" => {
+                self.pos += 1;
+                Ok(Expr::Value(Value::Bool(ident == "true")))
+            }
+            Some(Token::Ident(ident)) if ident == "nil" => {
+                self.pos += 1;
+                Ok(Expr::Value(Value::Nil))
+            }
+            Some(Token::Ident(_)) => {
+                let name = self.ident()?;
+                if self.is_symbol("(") {
+                    self.call(name, line)
+                } else {
+                    Ok(Expr::Var(name))
+                }
+            }
+            _ => Err(format!("line {}: expected an expression", line)),
+        }
+    }
+}
+
+fn parse(source: &str) -> Result<Vec<Stmt>, String> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        pos: 0,
+        depth: 0,
+    };
+    parser.block(&[])
+}
+
+pub struct Interpreter {
+    pub vars: HashMap<String, Value>,
+    steps: usize,
+}
+
+impl Interpreter {
+    pub fn new() -> Interpreter {
+        Interpreter {
+            vars: HashMap::new(),
+            steps: 0,
+        }
+    }
+
+    pub fn run(&mut self, source: &str, game: &mut Game) -> Result<(), String> {
+        let stmts = parse(source)?;
+        self.steps = 0;
+        self.exec_block(&stmts, game)
+    }
+
+    fn step(&mut self) -> Result<(), String> {
+        self.steps += 1;
+        if self.steps > MAX_STEPS {
+            return Err(format!("script exceeded {} steps", MAX_STEPS));
+        }
+        Ok(())
+    }
+
+    fn exec_block(&mut self, stmts: &[Stmt], game: &mut Game) -> Result<(), String> {
+        for stmt in stmts {
+            self.step()?;
+            match stmt {
+                Stmt::Assign(name, expr) => {
+                    let value = self.eval(expr, game)?;
+                    self.vars.insert(name.clone(), value);
+                }
+                Stmt::If(branches, otherwise) => {
+                    let mut body = otherwise;
+                    for (condition, branch) in branches {
+                        if self.eval(condition, game)?.is_truthy() {
+                            body = branch;
+                            break;
+                        }
+                    }
+                    self.exec_block(body, game)?;
+                }
+                Stmt::While(condition, body) => {
+                    while self.eval(condition, game)?.is_truthy() {
+                        self.step()?;
+                        self.exec_block(body, game)?;
+                    }
+                }
+                Stmt::Call(expr) => {
+                    self.eval(expr, game)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn eval(&mut self, expr: &Expr, game: &mut Game) -> Result<Value, String> {
+        match expr {
+            Expr::Value(value) => Ok(value.clone()),
+            Expr::Var(name) => Ok(self.vars.get(name).cloned().unwrap_or(Value::Nil)),
+            Expr::Not(expr) => Ok(Value::Bool(!self.eval(expr, game)?.is_truthy())),
+            Expr::Neg(expr) => match self.eval(expr, game)? {
+                Value::Number(v) => Ok(Value::Number(v.wrapping_neg())),
+                v => Err(format!("can not negate a {}", v.type_name())),
+            },
+            Expr::Binary(left, "and", right) => {
+                let left = self.eval(left, game)?;
+                if !left.is_truthy() {
+                    return Ok(left);
+                }
+                self.eval(right, game)
+            }
+            Expr::Binary(left, "or", right) => {
+                let left = self.eval(left, game)?;
+                if left.is_truthy() {
+                    return Ok(left);
+                }
+                self.eval(right, game)
+            }
+            Expr::Binary(left, op, right) => {
+                let left = self.eval(left, game)?;
+                let right = self.eval(right, game)?;
+                binary(&left, op, &right)
+            }
+            Expr::Call(name, args, line) => {
+                let mut values = vec![];
+                for arg in args {
+                    values.push(self.eval(arg, game)?);
+                }
+                self.call(name, &values, game)
+                    .map_err(|e| format!("line {}: {}: {}", line, name, e))
+            }
+        }
+    }
+
+    /// the api scripts can use to read and change the game
+    fn call(&mut self, name: &str, args: &[Value], game: &mut Game) -> Result<Value, String> {
+        let value = match name {
+            "message" => {
+                let text = args.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                game.info_queue.queue("Info".to_string(), text.join(" "));
+                Value::Nil
+            }
+            "map" => Value::Number(game.cur_map as i64),
+            "pos_i" => Value::Number(game.pos.i as i64),
+            "pos_j" => Value::Number(game.pos.j as i64),
+            "turn" => Value::Number(game.turn as i64),
+            "teleport" => {
+                let (map, j, i) = (index(args, 0)?, index(args, 1)?, index(args, 2)?);
+                let in_bounds = game
+                    .maps
+                    .get(map)
+                    .and_then(|map| map.get(j))
+                    .is_some_and(|row| i < row.len());
+                if !in_bounds {
+                    return Err(format!("{} {} {} is not on a map", map, j, i));
+                }
+                game.enter_map(map, Pos { i, j });
+                Value::Nil
+            }
+            "tile" => {
+                let (map, j, i) = (index(args, 0)?, index(args, 1)?, index(args, 2)?);
+                match game
+                    .maps
+                    .get(map)
+                    .and_then(|map| map.get(j))
+                    .and_then(|row| row.get(i))
+                {
+                    Some(block) => Value::Str(format!("{:?}", block)),
+                    None => Value::Nil,
+                }
+            }
+            "set_tile" => {
+                let (map, j, i) = (index(args, 0)?, index(args, 1)?, index(args, 2)?);
+                let block = string(args, 3)?;
+                game.apply_action(&crate::events::Action::Spawn(map, j, i, block));
+                Value::Nil
+            }
+            "hp" => Value::Number(game.entities[0].hp as i64),
+            "max_hp" => Value::Number(game.max_hp as i64),
+            "heal" => {
+                let amount = hp(args, 0)?;
+                game.entities[0].hp = game.entities[0].hp.saturating_add(amount).min(game.max_hp);
+                Value::Nil
+            }
+            "damage" => {
+                game.damage_player(hp(args, 0)?);
+                Value::Nil
+            }
+            "enemy_name" => match game.entities.get(index(args, 0)? + 1) {
+                Some(enemy) => Value::Str(enemy.name.clone()),
+                None => Value::Nil,
+            },
+            "enemy_hp" => match game.entities.get(index(args, 0)? + 1) {
+                Some(enemy) => Value::Number(enemy.hp as i64),
+                None => Value::Nil,
+            },
+            "start_fight" => {
+                let enemy_id = index(args, 0)?;
+                if enemy_id + 1 >= game.entities.len() {
+                    return Err(format!("enemy {} not found", enemy_id));
+                }
+                game.start_encounter(enemy_id);
+                Value::Nil
+            }
+            "has_item" => Value::Bool(game.inventory.position_of(index(args, 0)?).is_some()),
+            "give_item" => {
+                game.pick_up_item(index(args, 0)?)?;
+                Value::Nil
+            }
+            "take_item" => {
+                let taken = match game.inventory.position_of(index(args, 0)?) {
+                    Some(position) => game.inventory.remove_item(position).is_some(),
+                    None => false,
+                };
+                Value::Bool(taken)
+            }
+            "gold" => Value::Number(game.gold as i64),
+            "give_gold" => {
+                game.gold = game.gold.saturating_add(index(args, 0)?);
+                Value::Nil
+            }
+            "take_gold" => {
+                game.gold = game.gold.saturating_sub(index(args, 0)?);
+                Value::Nil
+            }
+            "flag" => Value::Bool(game.flags.is_set(&string(args, 0)?)),
+            "set_flag" => {
+                game.set_flag(string(args, 0)?);
+                Value::Nil
+            }
+            "unset_flag" => {
+                game.unset_flag(string(args, 0)?);
+                Value::Nil
+            }
+            "counter" => Value::Number(game.flags.get(&string(args, 0)?) as i64),
+            "add_counter" => {
+                game.add_to_counter(string(args, 0)?, number(args, 1)? as i32);
+                Value::Nil
+            }
+            _ => return Err("unknown function".to_string()),
+        };
+        Ok(value)
+    }
+}
+
+fn binary(left: &Value, op: &str, right: &Value) -> Result<Value, String> {
+    let value = match (left, op, right) {
+        (_, "==", _) => Value::Bool(left == right),
+        (_, "~=" | "!=", _) => Value::Bool(left != right),
+        (_, "..", _) => Value::Str(format!("{}{}", left, right)),
+        (Value::Number(a), _, Value::Number(b)) => match op {
+            "+" => Value::Number(a.wrapping_add(*b)),
+            "-" => Value::Number(a.wrapping_sub(*b)),
+            "*" => Value::Number(a.wrapping_mul(*b)),
+            "/" | "%" if *b == 0 => return Err("division by zero".to_string()),
+            "/" => Value::Number(a.wrapping_div(*b)),
+            "%" => Value::Number(a.wrapping_rem(*b)),
+            "<" => Value::Bool(a < b),
+            "<=" => Value::Bool(a <= b),
+            ">" => Value::Bool(a > b),
+            ">=" => Value::Bool(a >= b),
+            _ => return Err(format!("unknown operator {}", op)),
+        },
+        (Value::Str(a), "<" | "<=" | ">" | ">=", Value::Str(b)) => Value::Bool(match op {
+            "<" => a < b,
+            "<=" => a <= b,
+            ">" => a > b,
+            _ => a >= b,
+        }),
+        _ => {
+            return Err(format!(
+                "can not use {} on a {} and a {}",
+                op,
+                left.type_name(),
+                right.type_name()
+            ))
+        }
+    };
+    Ok(value)
+}
+
+fn number(args: &[Value], position: usize) -> Result<i64, String> {
+    match args.get(position) {
+        Some(Value::Number(v)) => Ok(*v),
+        Some(v) => Err(format!(
+            "argument {} must be a number, not a {}",
+            position + 1,
+            v.type_name()
+        )),
+        None => Err(format!("argument {} is missing", position + 1)),
+    }
+}
+
+fn index(args: &[Value], position: usize) -> Result<usize, String> {
+    let value = number(args, position)?;
+    usize::try_from(value).map_err(|_| format!("argument {} must not be negative", position + 1))
+}
+
+/// a number of hit points, clamped to what fits into them
+fn hp(args: &[Value], position: usize) -> Result<i32, String> {
+    Ok(number(args, position)?.clamp(i32::MIN.into(), i32::MAX.into()) as i32)
+}
+
+fn string(args: &[Value], position: usize) -> Result<String, String> {
+    match args.get(position) {
+        Some(Value::Str(v)) => Ok(v.clone()),
+        Some(v) => Err(format!(
+            "argument {} must be a string, not a {}",
+            position + 1,
+            v.type_name()
+        )),
+        None => Err(format!("argument {} is missing", position + 1)),
+    }
+}
+
+impl Game {
+    /// reads the files of all scripts which are not given inline
+    pub fn load_scripts(&mut self) {
+        let dir = std::path::Path::new(&self.scenario_path)
+            .parent()
+            .unwrap_or(std::path::Path::new(""));
+        for script in self.scripts.iter_mut() {
+            if let (None, Some(file)) = (&script.source, &script.file) {
+                match std::fs::read_to_string(dir.join(file)) {
+                    Ok(source) => script.source = Some(source),
+                    Err(e) => log::warn!("can not read script {}: {}", file, e),
+                }
+            }
+        }
+    }
+
+    pub fn run_script(&mut self, script_id: usize) {
+        let source = match self.scripts.iter().find(|script| script.id == script_id) {
+            Some(Script {
+                source: Some(source),
+                ..
+            }) => source.clone(),
+            _ => {
+                log::warn!("script {} not found", script_id);
+                return;
+            }
+        };
+        if let Err(e) = Interpreter::new().run(&source, self) {
+            log::warn!("script {}: {}", script_id, e);
+            self.info_queue.queue(
+                "Script error".to_string(),
+                format!("script {}: {}", script_id, e),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::Game;
+    use crate::script::{parse, Interpreter, Value, MAX_NESTING, MAX_STEPS};
+
+    fn run(source: &str) -> (Result<(), String>, Interpreter, Game) {
+        let mut game = Game::from_yaml("");
+        let mut interpreter = Interpreter::new();
+        let result = interpreter.run(source, &mut game);
+        (result, interpreter, game)
+    }
+
+    #[test]
+    fn test_expressions() {
+        let (result, interpreter, _) = run("
+            let a = 1 + 2 * 3 -- comment
+            let b = (1 + 2) * 3 % 5
+            let c = not (a > b) and 'x' .. a or false
+            let d = -a ~= 7
+        ");
+        result.unwrap();
+        assert_eq!(interpreter.vars["a"], Value::Number(7));
+        assert_eq!(interpreter.vars["b"], Value::Number(4));
+        assert_eq!(interpreter.vars["c"], Value::Bool(false));
+        assert_eq!(interpreter.vars["d"], Value::Bool(true));
+    }
+
+    #[test]
+    fn test_control_flow() {
+        let (result, interpreter, _) = run("
+            let i = 0
+            let sum = 0
+            while i < 10 do
+                i = i + 1
+                if i % 2 == 0 then
+                    sum = sum + i
+                elseif i == 5 then
+                    sum = sum + 100
+                else
+                    sum = sum - 1
+                end
+            end
+        ");
+        result.unwrap();
+        assert_eq!(
+            interpreter.vars["sum"],
+            Value::Number(2 + 4 + 6 + 8 + 10 + 100 - 4)
+        );
+    }
+
+    #[test]
+    fn test_game_api() {
+        let (result, _, game) = run("
+            give_item(0)
+            if has_item(0) then
+                set_flag('armed')
+                add_counter('swords', 2)
+            end
+            teleport(1, 2, 2)
+        ");
+        result.unwrap();
+        assert!(game.flags.is_set("armed"));
+        assert_eq!(game.flags.get("swords"), 2);
+        assert_eq!(game.cur_map, 1);
+    }
+
+    #[test]
+    fn test_run_script_from_file() {
+        let mut game = Game::from_yaml("scripts: [{id: 0, file: scripts/troll_den.script}]");
+        let gold = game.gold;
+        assert!(game.scripts[0].source.is_some());
+
+        for _ in 0..4 {
+            game.run_script(0);
+        }
+
+        assert_eq!(game.flags.get("troll_den_visits"), 4);
+        assert_eq!(game.gold, gold + 5);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let (result, _, _) = run("while true do end");
+        assert_eq!(result, Err(format!("script exceeded {} steps", MAX_STEPS)));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(parse("if true then").is_err());
+        assert!(parse("let = 3").is_err());
+        assert!(parse("message('open").is_err());
+        let (result, _, _) = run("\n\nteleport(-1, 0, 0)");
+        assert_eq!(
+            result,
+            Err("line 3: teleport: argument 1 must not be negative".to_string())
+        );
+        let (result, _, _) = run("launch_missiles()");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_large_numbers() {
+        let (result, interpreter, game) = run("
+            give_gold(9223372036854775807)
+            give_gold(9223372036854775807)
+            give_gold(9223372036854775807)
+            damage(9223372036854775807)
+            let a = -(-9223372036854775807 - 1) / -1
+        ");
+        result.unwrap();
+        assert_eq!(game.gold, usize::MAX);
+        assert_eq!(game.entities[0].hp, game.max_hp - i32::MAX);
+        assert_eq!(interpreter.vars["a"], Value::Number(i64::MIN));
+
+        let (result, _, game) = run("heal(9223372036854775807)");
+        result.unwrap();
+        assert_eq!(game.entities[0].hp, game.max_hp);
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| "(".repeat(depth) + "1" + &")".repeat(depth);
+        // the script itself is the first level
+        assert!(parse(&format!("let a = {}", nested(MAX_NESTING - 1))).is_ok());
+        assert_eq!(
+            parse(&format!("let a = {}", nested(1000))).err(),
+            Some(format!(
+                "line 1: nested more than {} levels deep",
+                MAX_NESTING
+            ))
+        );
+        assert!(parse(&format!("let a = {}1", "not ".repeat(1000))).is_err());
+        assert!(parse(&"if true then ".repeat(1000)).is_err());
+        assert!(parse(&format!("let a = 1{}", " + 1".repeat(MAX_NESTING - 1))).is_ok());
+        assert!(parse(&format!("let a = 1{}", " + 1".repeat(1000))).is_err());
+        assert!(parse(&format!("let a = true{}", " and true".repeat(1000))).is_err());
+    }
+}
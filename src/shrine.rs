@@ -0,0 +1,174 @@
+use crate::game::{Game, Pos};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stat {
+    At,
+    Aw,
+    Rs,
+    Perception,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Buff {
+    pub stat: Stat,
+    pub amount: i32,
+}
+
+/// fountains, shrines and rest points the player can use by walking into them
+#[derive(Deserialize, Debug, Clone)]
+pub struct ShrineProps {
+    pub id: usize,
+    pub name: String,
+    /// hp restored on every use, capped at the total health of the player
+    #[serde(default)]
+    pub heal: i32,
+    /// removes all status effects
+    #[serde(default)]
+    pub cure: bool,
+    /// granted only on the first use of every shrine tile
+    pub buff: Option<Buff>,
+    /// how often every tile can be used, unlimited if not set
+    pub uses: Option<usize>,
+    /// steps the player has to walk before the tile can be used again
+    #[serde(default)]
+    pub cooldown: usize,
+}
+
+/// usage of a single shrine tile
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ShrineState {
+    pub map: usize,
+    pub pos: Pos,
+    pub times_used: usize,
+    pub last_used: usize,
+}
+
+impl Game {
+    pub fn use_shrine(&mut self, pos: &Pos, shrine_id: usize) {
+        let shrine = match self.shrines.iter().find(|shrine| shrine.id == shrine_id) {
+            Some(v) => v.clone(),
+            None => {
+                log::warn!("shrine {} not found", shrine_id);
+                return;
+            }
+        };
+
+        let state = self.shrine_states.iter().position(|state| {
+            state.map == self.cur_map && state.pos.i == pos.i && state.pos.j == pos.j
+        });
+        if let Some(state) = state.map(|index| &self.shrine_states[index]) {
+            if shrine.uses.is_some_and(|uses| state.times_used >= uses) {
+                self.info_queue
+                    .queue(shrine.name.clone(), "Nothing happens anymore".to_string());
+                return;
+            }
+            if self.turn < state.last_used + shrine.cooldown {
+                self.info_queue.queue(
+                    shrine.name.clone(),
+                    format!(
+                        "Come back in {} steps",
+                        state.last_used + shrine.cooldown - self.turn
+                    ),
+                );
+                return;
+            }
+        }
+
+        if shrine.heal > 0 {
+            let player = &mut self.entities[0];
+            player.hp = (player.hp + shrine.heal).min(self.max_hp);
+            self.info_queue.queue(
+                shrine.name.clone(),
+                format!("You are healed by {} hp", shrine.heal),
+            );
+        }
+        if shrine.cure && !self.status_effects.is_empty() {
+            self.status_effects.clear();
+            self.info_queue
+                .queue(shrine.name.clone(), "You feel cleansed".to_string());
+        }
+
+        match state {
+            Some(index) => {
+                self.shrine_states[index].times_used += 1;
+                self.shrine_states[index].last_used = self.turn;
+            }
+            None => {
+                if let Some(buff) = &shrine.buff {
+                    self.apply_buff(buff);
+                    self.info_queue.queue(
+                        shrine.name.clone(),
+                        format!("You feel stronger ({:?} +{})", buff.stat, buff.amount),
+                    );
+                }
+                self.shrine_states.push(ShrineState {
+                    map: self.cur_map,
+                    pos: pos.clone(),
+                    times_used: 1,
+                    last_used: self.turn,
+                });
+            }
+        }
+    }
+
+    fn apply_buff(&mut self, buff: &Buff) {
+        let stat = match buff.stat {
+            Stat::At => &mut self.rp_properties.at,
+            Stat::Aw => &mut self.rp_properties.aw,
+            Stat::Rs => &mut self.rp_properties.rs,
+            Stat::Perception => &mut self.rp_properties.perception,
+        };
+        *stat += buff.amount;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, Pos};
+
+    fn shrine_game(shrine: &str) -> Game {
+        Game::from_yaml(&format!(
+            "shrines:\n    - {{id: 0, name: Fountain, heal: 5, {}}}\n",
+            shrine
+        ))
+    }
+
+    #[test]
+    fn test_heal_capped() {
+        let mut game = shrine_game("");
+        game.entities[0].hp = 28;
+
+        game.use_shrine(&Pos { i: 0, j: 0 }, 0);
+
+        assert_eq!(game.entities[0].hp, 30);
+    }
+
+    #[test]
+    fn test_uses_and_cooldown() {
+        let mut game = shrine_game("uses: 2, cooldown: 3");
+        let pos = Pos { i: 0, j: 0 };
+        game.entities[0].hp = 1;
+
+        game.use_shrine(&pos, 0);
+        game.use_shrine(&pos, 0);
+        assert_eq!(game.entities[0].hp, 6);
+
+        game.turn += 3;
+        game.use_shrine(&pos, 0);
+        game.turn += 3;
+        game.use_shrine(&pos, 0);
+        assert_eq!(game.entities[0].hp, 11);
+    }
+
+    #[test]
+    fn test_buff_once() {
+        let mut game = shrine_game("buff: {stat: at, amount: 2}");
+
+        game.use_shrine(&Pos { i: 0, j: 0 }, 0);
+        game.use_shrine(&Pos { i: 0, j: 0 }, 0);
+
+        assert_eq!(game.rp_properties.at, 3);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::game::Game;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusKind {
+    Poison,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    /// number of steps the effect lasts
+    pub turns: usize,
+    /// damage dealt to the player on every step
+    #[serde(default)]
+    pub damage: i32,
+}
+
+impl StatusEffect {
+    pub fn name(&self) -> &str {
+        match self.kind {
+            StatusKind::Poison => "poisoned",
+        }
+    }
+}
+
+impl Game {
+    pub fn add_status_effect(&mut self, effect: StatusEffect) {
+        self.info_queue
+            .queue("Status".to_string(), format!("You are {}", effect.name()));
+        self.status_effects.push(effect);
+    }
+
+    /// applies all status effects of the player once, called after every step
+    pub fn tick_status_effects(&mut self) {
+        let damage: i32 = self
+            .status_effects
+            .iter()
+            .fold(0, |total, effect| total.saturating_add(effect.damage));
+        for effect in self.status_effects.iter_mut() {
+            effect.turns = effect.turns.saturating_sub(1);
+        }
+        self.damage_player(damage);
+
+        let (active, expired): (Vec<StatusEffect>, Vec<StatusEffect>) = self
+            .status_effects
+            .drain(..)
+            .partition(|effect| effect.turns > 0);
+        self.status_effects = active;
+        for effect in expired {
+            self.info_queue.queue(
+                "Status".to_string(),
+                format!("You are no longer {}", effect.name()),
+            );
+        }
+    }
+}
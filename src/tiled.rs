@@ -0,0 +1,472 @@
+use crate::map_gen::{
+    Layout, LegendTile, MapDefinition, MapRef, RawTile, TeleportTarget, TileError,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use thiserror::Error;
+
+/// the flip bits tiled stores in the upper bits of a gid
+const FLIP_FLAGS: u32 = 0xe000_0000;
+
+/// a map made with the tiled editor, stored next to the scenario as .tmx or .json
+#[derive(Debug, Clone)]
+pub struct TiledMap {
+    /// path of the exported map, relative to the scenario file
+    pub file: String,
+    /// gids of the tiles as stored in the file and the tiles they stand for,
+    /// the first tileset usually starts at gid 1
+    pub tileset: HashMap<u32, LegendTile>,
+    /// the tiles of the map, filled from `file` when the scenario is loaded
+    pub cells: Vec<Vec<Cell>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    /// gid of the tile without the flip bits, unique across all tilesets of the map
+    Tile(u32),
+    /// an object placed on the tile, it hides the tiles below
+    Object(LegendTile),
+}
+
+impl Cell {
+    /// empty cells are walls, tiles are looked up in the tileset of the map
+    pub fn raw(&self, tileset: &HashMap<u32, LegendTile>) -> Result<RawTile, TileError> {
+        match self {
+            Cell::Empty => LegendTile::Wall.raw(),
+            Cell::Tile(id) => match tileset.get(id) {
+                Some(tile) => tile.raw(),
+                None => Err(TileError::NotInTileset(*id)),
+            },
+            Cell::Object(tile) => tile.raw(),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TiledError {
+    #[error("can not read {file}: {source}")]
+    Read {
+        file: String,
+        source: std::io::Error,
+    },
+    #[error("{file} is not a valid tmx file: {message}")]
+    Xml { file: String, message: String },
+    #[error("{file} is not a valid tiled json file: {source}")]
+    Json {
+        file: String,
+        source: serde_json::Error,
+    },
+    #[error("{file}: {message}")]
+    Map { file: String, message: String },
+    #[error("{0} is neither a .tmx nor a .json file")]
+    Format(String),
+}
+
+/// the parts of a tiled map which are used, the same for both formats
+#[derive(Debug, Default)]
+struct Document {
+    width: usize,
+    height: usize,
+    tile_width: f64,
+    tile_height: f64,
+    /// gids of each tile layer, row by row
+    layers: Vec<Vec<u32>>,
+    objects: Vec<Object>,
+}
+
+#[derive(Debug, Default)]
+struct Object {
+    id: u32,
+    name: String,
+    /// `type` in older versions of tiled, `class` in newer ones
+    kind: String,
+    x: f64,
+    y: f64,
+    /// tile objects are placed by their bottom left corner
+    gid: Option<u32>,
+}
+
+impl TiledMap {
+    /// reads `file` relative to `dir` and fills in the cells
+    pub fn load(&mut self, dir: &Path) -> Result<(), TiledError> {
+        let contents =
+            std::fs::read_to_string(dir.join(&self.file)).map_err(|source| TiledError::Read {
+                file: self.file.clone(),
+                source,
+            })?;
+        let document = if self.file.ends_with(".tmx") {
+            parse_tmx(&contents).map_err(|message| TiledError::Xml {
+                file: self.file.clone(),
+                message,
+            })?
+        } else if self.file.ends_with(".json") || self.file.ends_with(".tmj") {
+            parse_json(&contents).map_err(|source| TiledError::Json {
+                file: self.file.clone(),
+                source,
+            })?
+        } else {
+            return Err(TiledError::Format(self.file.clone()));
+        };
+        self.cells = cells(&document).map_err(|message| TiledError::Map {
+            file: self.file.clone(),
+            message,
+        })?;
+        Ok(())
+    }
+}
+
+/// loads every map of the scenario which was made in tiled, returns the index of the map which failed
+pub fn load_maps(definitions: &mut [MapDefinition], dir: &Path) -> Result<(), (usize, TiledError)> {
+    for (index, definition) in definitions.iter_mut().enumerate() {
+        if let Layout::Tiled(tiled) = &mut definition.layout {
+            tiled.load(dir).map_err(|e| (index, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// lays the tile layers over each other and puts the objects on top
+fn cells(document: &Document) -> Result<Vec<Vec<Cell>>, String> {
+    let mut cells = vec![vec![Cell::Empty; document.width]; document.height];
+    for layer in &document.layers {
+        if layer.len() != document.width * document.height {
+            return Err(format!(
+                "a layer has {} tiles, but the map is {}x{}",
+                layer.len(),
+                document.width,
+                document.height
+            ));
+        }
+        for (index, gid) in layer.iter().enumerate() {
+            // gid 0 is an empty tile
+            match gid & !FLIP_FLAGS {
+                0 => {}
+                gid => cells[index / document.width][index % document.width] = Cell::Tile(gid),
+            }
+        }
+    }
+
+    for object in &document.objects {
+        let tile = object_tile(object).map_err(|e| format!("object {}: {}", object.id, e))?;
+        let i = (object.x / document.tile_width).floor();
+        let mut j = (object.y / document.tile_height).floor();
+        if object.gid.is_some() {
+            j -= 1.0;
+        }
+        if i < 0.0 || j < 0.0 || i as usize >= document.width || j as usize >= document.height {
+            return Err(format!("object {} is outside of the map", object.id));
+        }
+        cells[j as usize][i as usize] = Cell::Object(tile);
+    }
+    Ok(cells)
+}
+
+/// the kind of an object is a legend tile, its name the value like `3` or `crypt.entrance`
+fn object_tile(object: &Object) -> Result<LegendTile, String> {
+    let name = object.name.trim();
+    let number = || {
+        name.parse::<usize>()
+            .map_err(|_| format!("the name of a {} has to be a number", object.kind))
+    };
+    let tile = match object.kind.as_str() {
+        "path" => LegendTile::Path,
+        "wall" => LegendTile::Wall,
+        "anchor" => LegendTile::Anchor(name.to_string()),
+        "flag" => LegendTile::Flag(name.to_string()),
+        "new_map" => LegendTile::NewMap(match name.parse() {
+            Ok(index) => MapRef::Index(index),
+            Err(_) => MapRef::Name(name.to_string()),
+        }),
+        "teleport" => {
+            let numbers: Result<Vec<usize>, _> =
+                name.split(',').map(|part| part.trim().parse()).collect();
+            match numbers.as_deref() {
+                Ok(&[map, j, i]) => LegendTile::Teleport(TeleportTarget::Position([map, j, i])),
+                _ => LegendTile::Teleport(TeleportTarget::Anchor(name.to_string())),
+            }
+        }
+        "item" => LegendTile::Item(number()?),
+        "enemy" => LegendTile::Enemy(number()?),
+        "gold" => LegendTile::Gold(number()?),
+        "merchant" => LegendTile::Merchant(number()?),
+        "door" => LegendTile::Door(number()?),
+        "lever" => LegendTile::Lever(number()?),
+        "chest" => LegendTile::Chest(number()?),
+        "trap" => LegendTile::Trap(number()?),
+        "shrine" => LegendTile::Shrine(number()?),
+        "npc" => LegendTile::Npc(number()?),
+        "readable" => LegendTile::Readable(number()?),
+        "conditional" => LegendTile::Conditional(number()?),
+        "stairs_down" => LegendTile::StairsDown,
+        "stairs_up" => LegendTile::StairsUp,
+        "" => return Err("the object has no type".to_string()),
+        kind => return Err(format!("unknown object type '{}'", kind)),
+    };
+    Ok(tile)
+}
+
+#[derive(Deserialize)]
+struct JsonMap {
+    width: usize,
+    height: usize,
+    tilewidth: f64,
+    tileheight: f64,
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    data: Vec<u32>,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    /// layers of a group layer
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+#[derive(Deserialize)]
+struct JsonObject {
+    id: u32,
+    #[serde(default)]
+    name: String,
+    #[serde(default, rename = "type")]
+    kind: String,
+    #[serde(default)]
+    class: String,
+    x: f64,
+    y: f64,
+    gid: Option<u32>,
+}
+
+fn parse_json(contents: &str) -> Result<Document, serde_json::Error> {
+    let map: JsonMap = serde_json::from_str(contents)?;
+    let mut document = Document {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        ..Document::default()
+    };
+    add_json_layers(&mut document, map.layers);
+    Ok(document)
+}
+
+fn add_json_layers(document: &mut Document, layers: Vec<JsonLayer>) {
+    for layer in layers {
+        match layer.kind.as_str() {
+            "tilelayer" => document.layers.push(layer.data),
+            "objectgroup" => {
+                for object in layer.objects {
+                    document.objects.push(Object {
+                        id: object.id,
+                        name: object.name,
+                        kind: if object.class.is_empty() {
+                            object.kind
+                        } else {
+                            object.class
+                        },
+                        x: object.x,
+                        y: object.y,
+                        gid: object.gid,
+                    });
+                }
+            }
+            "group" => add_json_layers(document, layer.layers),
+            _ => {}
+        }
+    }
+}
+
+fn number(node: roxmltree::Node, key: &str) -> Result<f64, String> {
+    match node.attribute(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| format!("{} '{}' is not a number", key, value)),
+        None => Err(format!("{} is missing", key)),
+    }
+}
+
+fn parse_tmx(contents: &str) -> Result<Document, String> {
+    let xml = roxmltree::Document::parse(contents).map_err(|e| e.to_string())?;
+    let map = xml.root_element();
+    if !map.has_tag_name("map") {
+        return Err(format!("expected a map, not a {}", map.tag_name().name()));
+    }
+    let mut document = Document {
+        width: number(map, "width")? as usize,
+        height: number(map, "height")? as usize,
+        tile_width: number(map, "tilewidth")?,
+        tile_height: number(map, "tileheight")?,
+        ..Document::default()
+    };
+    add_tmx_layers(&mut document, map)?;
+    Ok(document)
+}
+
+/// only the layers of the map and its groups, tiles in a tileset can hold objects as well
+fn add_tmx_layers(document: &mut Document, parent: roxmltree::Node) -> Result<(), String> {
+    for node in parent.children().filter(|node| node.is_element()) {
+        match node.tag_name().name() {
+            "layer" => {
+                let data = node
+                    .children()
+                    .find(|child| child.has_tag_name("data"))
+                    .ok_or("a tile layer has no data")?;
+                if data.attribute("encoding") != Some("csv") {
+                    return Err(
+                        "only csv encoded layers can be read, change the tile layer format in tiled"
+                            .to_string(),
+                    );
+                }
+                let gids: Result<Vec<u32>, _> = data
+                    .text()
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|gid| !gid.is_empty())
+                    .map(str::parse::<u32>)
+                    .collect();
+                document
+                    .layers
+                    .push(gids.map_err(|_| "the layer data is not a list of numbers")?);
+            }
+            "objectgroup" => {
+                for object in node.children().filter(|child| child.has_tag_name("object")) {
+                    document.objects.push(Object {
+                        id: number(object, "id")? as u32,
+                        name: object.attribute("name").unwrap_or_default().to_string(),
+                        kind: object
+                            .attribute("class")
+                            .or_else(|| object.attribute("type"))
+                            .unwrap_or_default()
+                            .to_string(),
+                        x: number(object, "x")?,
+                        y: number(object, "y")?,
+                        gid: match object.attribute("gid") {
+                            Some(_) => Some(number(object, "gid")? as u32),
+                            None => None,
+                        },
+                    });
+                }
+            }
+            "group" => add_tmx_layers(document, node)?,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::map_gen::{LegendTile, TeleportTarget};
+    use crate::tiled::{cells, parse_json, parse_tmx, Cell};
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="3" height="2" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" source="dungeon.tsx"/>
+ <layer id="1" name="floor" width="3" height="2">
+  <data encoding="csv">
+1,1,1,
+0,1,2147483650
+</data>
+ </layer>
+ <!-- things on the map -->
+ <objectgroup id="2" name="things">
+  <object id="1" name="4" type="item" x="16" y="0" width="16" height="16"/>
+  <object id="2" name="crypt.entrance" class="teleport" gid="3" x="32" y="32"/>
+ </objectgroup>
+</map>"#;
+
+    #[test]
+    fn test_tmx() {
+        let cells = cells(&parse_tmx(TMX).unwrap()).unwrap();
+
+        assert_eq!(
+            cells,
+            vec![
+                vec![
+                    Cell::Tile(1),
+                    Cell::Object(LegendTile::Item(4)),
+                    Cell::Tile(1)
+                ],
+                vec![
+                    Cell::Empty,
+                    Cell::Tile(1),
+                    Cell::Object(LegendTile::Teleport(TeleportTarget::Anchor(
+                        "crypt.entrance".to_string()
+                    )))
+                ],
+            ]
+        );
+        assert!(parse_tmx(&TMX.replace("csv", "base64")).is_err());
+    }
+
+    #[test]
+    fn test_json() {
+        let document = parse_json(
+            r#"{"width": 2, "height": 1, "tilewidth": 8, "tileheight": 8,
+                "tilesets": [{"firstgid": 1, "source": "dungeon.tsj"}, {"firstgid": 5, "source": "doors.tsj"}],
+                "layers": [
+                    {"type": "tilelayer", "data": [1, 2]},
+                    {"type": "tilelayer", "data": [5, 0]},
+                    {"type": "group", "layers": [{"type": "objectgroup", "objects": [
+                        {"id": 7, "name": "1,9,5", "type": "teleport", "x": 8, "y": 0}
+                    ]}]}
+                ]}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            cells(&document).unwrap(),
+            vec![vec![
+                Cell::Tile(5),
+                Cell::Object(LegendTile::Teleport(TeleportTarget::Position([1, 9, 5])))
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_xml_syntax() {
+        let tmx = TMX
+            .replace("1,1,1,", "<![CDATA[1,1,1,")
+            .replace("2147483650\n", "2147483650]]>\n")
+            .replace("crypt.entrance", "crypt &amp; &lt;cellar> &#x61;")
+            .replace(
+                "<layer id=\"1\"",
+                "<tileset firstgid=\"4\" name=\"doors\" tilewidth=\"16\" tileheight=\"16\" tilecount=\"1\" columns=\"1\">
+  <tile id=\"0\"><objectgroup><object id=\"9\" x=\"0\" y=\"0\"/></objectgroup></tile>
+ </tileset>
+ <layer id=\"1\"",
+            );
+        let cells = cells(&parse_tmx(&tmx).unwrap()).unwrap();
+
+        assert_eq!(cells[0][0], Cell::Tile(1));
+        assert_eq!(cells[1][1], Cell::Tile(1));
+        assert_eq!(
+            cells[1][2],
+            Cell::Object(LegendTile::Teleport(TeleportTarget::Anchor(
+                "crypt & <cellar> a".to_string()
+            )))
+        );
+        assert!(parse_tmx(&TMX.replace("</map>", "")).is_err());
+    }
+
+    #[test]
+    fn test_object_errors() {
+        let document = parse_tmx(&TMX.replace("type=\"item\"", "type=\"dragon\"")).unwrap();
+        assert_eq!(
+            cells(&document).unwrap_err(),
+            "object 1: unknown object type 'dragon'"
+        );
+        let document = parse_tmx(&TMX.replace("x=\"32\"", "x=\"320\"")).unwrap();
+        assert_eq!(
+            cells(&document).unwrap_err(),
+            "object 2 is outside of the map"
+        );
+    }
+}
@@ -0,0 +1,249 @@
+use crate::game::{Game, MapBlockTypes, Pos};
+use rand::prelude::*;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrapKind {
+    Spike,
+    PoisonDart,
+    /// drops the player to `target`
+    Pit,
+    /// spawns an encounter with `enemy`
+    Alarm,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TrapProps {
+    pub id: usize,
+    pub kind: TrapKind,
+    #[serde(default)]
+    pub damage: i32,
+    /// status effect applied by a poison dart
+    pub effect: Option<crate::status::StatusEffect>,
+    /// map, j and i the player falls to, like TeleportTrigger
+    pub target: Option<[usize; 3]>,
+    /// enemy_id in enemies called by an alarm
+    pub enemy: Option<usize>,
+    /// hidden traps have to be spotted before they are shown on the map
+    #[serde(default = "default_hidden")]
+    pub hidden: bool,
+    /// value the perception roll has to reach to spot the trap
+    #[serde(default = "default_difficulty")]
+    pub difficulty: i32,
+}
+
+fn default_hidden() -> bool {
+    true
+}
+
+fn default_difficulty() -> i32 {
+    15
+}
+
+impl Game {
+    fn get_trap_props(&self, trap_id: usize) -> Option<TrapProps> {
+        self.traps.iter().find(|trap| trap.id == trap_id).cloned()
+    }
+
+    /// shows all traps on all maps which are not hidden by definition
+    pub fn reveal_visible_traps(&mut self) {
+        let visible: Vec<usize> = self
+            .traps
+            .iter()
+            .filter(|trap| !trap.hidden)
+            .map(|trap| trap.id)
+            .collect();
+        for map in self.maps.iter_mut() {
+            for block in map.iter_mut().flatten() {
+                if let MapBlockTypes::Trap(trap_id, false) = block {
+                    if visible.contains(trap_id) {
+                        *block = MapBlockTypes::Trap(*trap_id, true);
+                    }
+                }
+            }
+        }
+    }
+
+    /// rolls a perception check for every hidden trap next to the player
+    pub fn search_for_traps(&mut self) {
+        let map = &self.maps[self.cur_map];
+        let mut hidden = vec![];
+        for j in self.pos.j.saturating_sub(1)..=self.pos.j + 1 {
+            for i in self.pos.i.saturating_sub(1)..=self.pos.i + 1 {
+                if let Some(&MapBlockTypes::Trap(trap_id, false)) =
+                    map.get(j).and_then(|row| row.get(i))
+                {
+                    let difficulty = self
+                        .get_trap_props(trap_id)
+                        .map_or(default_difficulty(), |trap| trap.difficulty);
+                    hidden.push((Pos { i, j }, trap_id, difficulty));
+                }
+            }
+        }
+
+        for (pos, trap_id, difficulty) in hidden {
+            if self.rng.gen_range(1, 21) + self.rp_properties.perception < difficulty {
+                continue;
+            }
+            self.set_map_block(&pos, MapBlockTypes::Trap(trap_id, true));
+            self.info_queue
+                .queue("Trap".to_string(), "You have spotted a trap".to_string());
+        }
+    }
+
+    /// triggers the trap the player is standing on, visible traps are stepped around
+    pub fn step_on_trap(&mut self, trap_id: usize, visible: bool) {
+        if visible {
+            self.info_queue.queue(
+                "Trap".to_string(),
+                "You carefully avoid the trap".to_string(),
+            );
+            return;
+        }
+        let trap = match self.get_trap_props(trap_id) {
+            Some(v) => v,
+            None => {
+                log::warn!("trap {} not found", trap_id);
+                return;
+            }
+        };
+        self.set_map_block(&self.pos.clone(), MapBlockTypes::Trap(trap_id, true));
+
+        match trap.kind {
+            TrapKind::Spike => self.info_queue.queue(
+                "Trap".to_string(),
+                format!("Spikes hit you for {} damage", trap.damage),
+            ),
+            TrapKind::PoisonDart => {
+                self.info_queue.queue(
+                    "Trap".to_string(),
+                    format!("A dart hits you for {} damage", trap.damage),
+                );
+                if let Some(effect) = trap.effect {
+                    self.add_status_effect(effect);
+                }
+            }
+            TrapKind::Pit => {
+                self.info_queue
+                    .queue("Trap".to_string(), "You fall through a pit".to_string());
+                match trap.target {
+                    Some([map, j, i]) if self.has_tile(map, j, i) => {
+                        self.enter_map(map, Pos { i, j })
+                    }
+                    Some([map, j, i]) => {
+                        log::warn!(
+                            "trap {} drops to {} {} {}, which is outside of the maps",
+                            trap_id,
+                            map,
+                            j,
+                            i
+                        )
+                    }
+                    None => {}
+                }
+            }
+            TrapKind::Alarm => {
+                self.info_queue
+                    .queue("Trap".to_string(), "An alarm goes off".to_string());
+                if let Some(enemy_id) = trap.enemy {
+                    self.start_encounter(enemy_id);
+                }
+            }
+        }
+        self.damage_player(trap.damage);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{Game, MapBlockTypes, Movement};
+    use crate::MenuItem;
+
+    /// the player stands west of the trap
+    fn trap_game(trap: &str) -> Game {
+        Game::from_yaml(&format!(
+            "
+maps:
+    - '|_|_|_|_|_|_| |_|_|_|_|_|_| |_|_|_|_|_|_| |_|_|_|_|P0|_|'
+    - '|_|_|_| |_|_|_| |_|_|_|'
+start_pos: [3, 3]
+traps:
+    - {}
+",
+            trap
+        ))
+    }
+
+    #[test]
+    fn test_poison_dart_trap() {
+        let mut game = trap_game(
+            "{id: 0, kind: poison_dart, damage: 2, difficulty: 100,
+       effect: {kind: poison, turns: 2, damage: 1}}",
+        );
+
+        game.east();
+        assert_eq!(game.maps[0][3][4], MapBlockTypes::Trap(0, true));
+        assert_eq!(game.entities[0].hp, 30 - 2 - 1);
+
+        game.west();
+        assert_eq!(game.entities[0].hp, 30 - 2 - 2);
+        assert!(game.status_effects.is_empty());
+    }
+
+    #[test]
+    fn test_lethal_poison() {
+        let mut game = trap_game(
+            "{id: 0, kind: poison_dart, damage: 1, difficulty: 100,
+       effect: {kind: poison, turns: 3, damage: 40}}",
+        );
+
+        game.east();
+
+        assert!(game.is_defeated());
+        assert_eq!(game.active_menu_item, MenuItem::Saves);
+    }
+
+    #[test]
+    fn test_pit_trap() {
+        let mut game =
+            trap_game("{id: 0, kind: pit, damage: 2, difficulty: 100, target: [1, 2, 2]}");
+
+        game.east();
+
+        assert_eq!(game.cur_map, 1);
+        assert_eq!((game.pos.j, game.pos.i), (2, 2));
+    }
+
+    #[test]
+    fn test_pit_trap_outside_of_the_maps() {
+        let mut game =
+            trap_game("{id: 0, kind: pit, damage: 2, difficulty: 100, target: [1, 2, 9]}");
+
+        game.east();
+
+        assert_eq!(game.cur_map, 0);
+        assert_eq!((game.pos.j, game.pos.i), (3, 4));
+        assert_eq!(game.entities[0].hp, 28);
+    }
+
+    #[test]
+    fn test_lethal_trap() {
+        let mut game = trap_game("{id: 0, kind: spike, damage: 40, difficulty: 100}");
+
+        game.east();
+
+        assert!(game.is_defeated());
+        assert_eq!(game.active_menu_item, MenuItem::Saves);
+    }
+
+    #[test]
+    fn test_spot_trap() {
+        let mut game = trap_game("{id: 0, kind: spike, damage: 2, difficulty: -100}");
+
+        game.search_for_traps();
+        game.east();
+
+        assert_eq!(game.entities[0].hp, 30);
+    }
+}
@@ -0,0 +1,1225 @@
+use crate::custom_layer::GameSettings;
+use crate::edges::{EdgeError, Edges};
+use crate::events::Action;
+use crate::game::{MapBlockTypes, Pos};
+use crate::map_gen::{Layout, Map, Names, RawTile, TileError};
+use crate::procgen::Generator;
+use crate::quest::Objective;
+use crate::reachability::Reachability;
+use crate::tiled::TiledMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// a problem found in a scenario file, line and column start at 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, severity, self.message
+        )
+    }
+}
+
+/// a tile of a map together with the place it was written in the scenario file
+struct Tile {
+    text: String,
+    line: usize,
+    column: usize,
+    /// where the tile is on a map made in tiled or generated, those are reported at the start of the map
+    origin: Option<String>,
+}
+
+/// reads and checks a scenario file, an unreadable file is reported as a diagnostic as well
+pub fn validate_file(path: &str) -> Vec<Diagnostic> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => {
+            let dir = std::path::Path::new(path)
+                .parent()
+                .unwrap_or(std::path::Path::new(""));
+            validate_scenario(&contents, dir)
+        }
+        Err(e) => vec![Diagnostic {
+            severity: Severity::Error,
+            line: 1,
+            column: 1,
+            message: format!("can not read the file: {}", e),
+        }],
+    }
+}
+
+/// collects all problems of a scenario instead of stopping at the first one,
+/// maps made in tiled are read relative to `dir`
+pub fn validate_scenario(contents: &str, dir: &std::path::Path) -> Vec<Diagnostic> {
+    let mut settings: GameSettings = match serde_yaml::from_str(contents) {
+        Ok(v) => v,
+        Err(e) => {
+            let (line, column) = e.location().map_or((1, 1), |l| (l.line(), l.column()));
+            return vec![Diagnostic {
+                severity: Severity::Error,
+                line,
+                column,
+                message: e.to_string(),
+            }];
+        }
+    };
+
+    let load_errors = load_maps(contents, &mut settings, dir);
+    let mut validator = Validator {
+        contents,
+        settings: &settings,
+        diagnostics: load_errors,
+    };
+    let (maps, names) = validator.check_maps();
+    validator.check_start_pos(&maps);
+    validator.check_references(&maps, &names);
+    let maps = known_blocks(&maps);
+    let edges = validator.check_edges(&maps);
+    let levels = validator.check_dungeon(&maps);
+    validator.check_reachability(&maps, &edges, &levels);
+
+    let mut diagnostics = validator.diagnostics;
+    diagnostics.sort_by_key(|d| (d.line, d.column));
+    diagnostics
+}
+
+struct Validator<'a> {
+    contents: &'a str,
+    settings: &'a GameSettings,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Validator<'_> {
+    fn report(&mut self, severity: Severity, line: usize, column: usize, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            line,
+            column,
+            message,
+        });
+    }
+
+    /// reports a problem with a tile, tiles of maps made in tiled or generated say where they are
+    fn report_tile(&mut self, severity: Severity, tile: &Tile, message: String) {
+        let message = match &tile.origin {
+            Some(origin) => format!("{}: {}", origin, message),
+            None => message,
+        };
+        self.report(severity, tile.line, tile.column, message);
+    }
+
+    /// line of a top level key like `start_pos:`
+    fn key_line(&self, key: &str) -> usize {
+        self.contents
+            .lines()
+            .position(|line| line.starts_with(&format!("{}:", key)))
+            .map_or(1, |index| index + 1)
+    }
+
+    /// splits the maps into tiles the same way map_gen does
+    /// and finds out where each of them was written
+    fn map_tiles(&self) -> Vec<Vec<Vec<Tile>>> {
+        let sources = map_source_lines(self.contents);
+        let lines: Vec<&str> = self.contents.lines().collect();
+
+        let mut maps = vec![];
+        for (m, definition) in self.settings.maps.iter().enumerate() {
+            let source = sources.get(m).cloned().unwrap_or_default();
+            let map = match &definition.layout {
+                Layout::Pipes(map_str) => pipe_tiles(map_str, &source, &lines),
+                Layout::Grid(grid) => grid_tiles(grid, &source, &lines),
+                Layout::Tiled(tiled) => tiled_tiles(tiled, &source),
+                Layout::Generated(generator) => generated_tiles(m, generator, &source),
+            };
+            maps.push(map);
+        }
+        maps
+    }
+
+    fn parse_tile(&self, map: usize, j: usize, i: usize, text: &str) -> Result<RawTile, TileError> {
+        let definition = &self.settings.maps[map];
+        match &definition.layout {
+            Layout::Pipes(_) | Layout::Generated(_) => crate::map_gen::parse_raw_tile(text),
+            Layout::Grid(_) => {
+                let legend = definition.legend(&self.settings.legend);
+                let c = text.chars().next().unwrap_or(' ');
+                crate::map_gen::parse_grid_tile(c, &legend)
+            }
+            Layout::Tiled(tiled) => tiled.cells[j][i].raw(&tiled.tileset),
+        }
+    }
+
+    /// names of the maps and anchors, the ones used twice are reported
+    fn collect_names(
+        &mut self,
+        tiles: &[Vec<Vec<Tile>>],
+        raw: &[Vec<Vec<Option<RawTile>>>],
+    ) -> Names {
+        let mut names = Names::default();
+        let sources = map_source_lines(self.contents);
+        for (m, definition) in self.settings.maps.iter().enumerate() {
+            if let Some(name) = &definition.name {
+                if !names.add_map(name, m) {
+                    let line = sources.get(m).map_or(1, |source| source.start);
+                    self.report(
+                        Severity::Error,
+                        line,
+                        1,
+                        format!("there is already a map called '{}'", name),
+                    );
+                }
+            }
+        }
+        for (m, map) in raw.iter().enumerate() {
+            for (j, row) in map.iter().enumerate() {
+                for (i, tile) in row.iter().enumerate() {
+                    if let Some(RawTile::Anchor(name)) = tile {
+                        if let Err(e) = names.add_anchor(m, name, j, i) {
+                            let tile = &tiles[m][j][i];
+                            self.report_tile(Severity::Error, tile, e.to_string());
+                        }
+                    }
+                }
+            }
+        }
+        names
+    }
+
+    /// the blocks of the maps and the names in them, blocks which could not be read are None
+    fn check_maps(&mut self) -> (Vec<Vec<Vec<Option<MapBlockTypes>>>>, Names) {
+        let tiles = self.map_tiles();
+        if tiles.is_empty() {
+            let line = self.key_line("maps");
+            self.report(
+                Severity::Error,
+                line,
+                1,
+                "the scenario has no maps".to_string(),
+            );
+        }
+
+        let mut raw = vec![];
+        for (m, map) in tiles.iter().enumerate() {
+            let mut raw_map = vec![];
+            for (j, row) in map.iter().enumerate() {
+                if let (Some(first), Some(tile)) = (map.first(), row.first()) {
+                    if row.len() != first.len() {
+                        self.report(
+                            Severity::Error,
+                            tile.line,
+                            tile.column,
+                            format!(
+                                "row {} of map {} has {} tiles, but the first row has {}",
+                                j,
+                                m,
+                                row.len(),
+                                first.len()
+                            ),
+                        );
+                    }
+                }
+
+                let mut raw_row = vec![];
+                for (i, tile) in row.iter().enumerate() {
+                    match self.parse_tile(m, j, i, &tile.text) {
+                        Ok(raw_tile) => raw_row.push(Some(raw_tile)),
+                        Err(e) => {
+                            self.report_tile(Severity::Error, tile, e.to_string());
+                            raw_row.push(None);
+                        }
+                    }
+                }
+                raw_map.push(raw_row);
+            }
+            raw.push(raw_map);
+        }
+
+        let names = self.collect_names(&tiles, &raw);
+        let mut maps = vec![];
+        for (m, raw_map) in raw.iter().enumerate() {
+            let mut blocks = vec![];
+            for (j, raw_row) in raw_map.iter().enumerate() {
+                let mut row_blocks = vec![];
+                for (i, raw_tile) in raw_row.iter().enumerate() {
+                    let block = match raw_tile.as_ref().map(|raw_tile| raw_tile.resolve(&names)) {
+                        Some(Ok(block)) => Some(block),
+                        Some(Err(e)) => {
+                            let tile = &tiles[m][j][i];
+                            self.report_tile(Severity::Error, tile, e.to_string());
+                            None
+                        }
+                        None => None,
+                    };
+                    row_blocks.push(block);
+                }
+                blocks.push(row_blocks);
+            }
+            maps.push(blocks);
+        }
+
+        for (m, map) in tiles.iter().enumerate() {
+            for (j, row) in map.iter().enumerate() {
+                for (i, tile) in row.iter().enumerate() {
+                    if let Some(block) = &maps[m][j][i] {
+                        for (severity, message) in self.check_block(block, &maps) {
+                            self.report_tile(severity, tile, message);
+                        }
+                    }
+                }
+            }
+        }
+        (maps, names)
+    }
+
+    /// problems with the things a single tile refers to
+    fn check_block(
+        &self,
+        block: &MapBlockTypes,
+        maps: &[Vec<Vec<Option<MapBlockTypes>>>],
+    ) -> Vec<(Severity, String)> {
+        let s = self.settings;
+        let defined = |kind: &str, found: bool, id: &usize| {
+            if found {
+                vec![]
+            } else {
+                vec![(Severity::Error, format!("{} {} is not defined", kind, id))]
+            }
+        };
+        match block {
+            MapBlockTypes::NewMapTrigger(map) if *map >= maps.len() => {
+                vec![(Severity::Error, format!("map {} does not exist", map))]
+            }
+            MapBlockTypes::TeleportTrigger(map, j, i) => check_target(maps, *map, *j, *i),
+            MapBlockTypes::ItemTrigger(id) => self.check_item(id),
+            MapBlockTypes::EnemyTrigger(id) => self.check_enemy(id),
+            MapBlockTypes::MerchantTrigger(id) => {
+                defined("merchant", s.merchants.iter().any(|x| x.id == *id), id)
+            }
+            MapBlockTypes::Door(id, _) => defined("door", s.doors.iter().any(|x| x.id == *id), id),
+            MapBlockTypes::Lever(id, _) => {
+                defined("lever", s.levers.iter().any(|x| x.id == *id), id)
+            }
+            MapBlockTypes::Chest(id) => defined("chest", s.chests.iter().any(|x| x.id == *id), id),
+            MapBlockTypes::Trap(id, _) => defined("trap", s.traps.iter().any(|x| x.id == *id), id),
+            MapBlockTypes::Shrine(id) => {
+                defined("shrine", s.shrines.iter().any(|x| x.id == *id), id)
+            }
+            MapBlockTypes::Npc(id) => defined("npc", s.npcs.iter().any(|x| x.id == *id), id),
+            MapBlockTypes::Conditional(id) => defined(
+                "conditional tile",
+                s.conditional_tiles.iter().any(|x| x.id == *id),
+                id,
+            ),
+            MapBlockTypes::Readable(id) => {
+                defined("readable", s.readables.iter().any(|x| x.id == *id), id)
+            }
+            _ => vec![],
+        }
+    }
+
+    fn check_item(&self, id: &usize) -> Vec<(Severity, String)> {
+        if self.settings.global_items.iter().any(|item| item.id == *id) {
+            return vec![];
+        }
+        vec![(
+            Severity::Error,
+            format!("item {} is not in global_items", id),
+        )]
+    }
+
+    fn check_enemy(&self, id: &usize) -> Vec<(Severity, String)> {
+        if *id < self.settings.enemies.len() {
+            return vec![];
+        }
+        vec![(Severity::Error, format!("enemy {} is not in enemies", id))]
+    }
+
+    fn check_start_pos(&mut self, maps: &[Vec<Vec<Option<MapBlockTypes>>>]) {
+        let [i, j] = self.settings.start_pos;
+        let line = self.key_line("start_pos");
+        let block = maps
+            .first()
+            .and_then(|map| map.get(j))
+            .and_then(|row| row.get(i));
+        match block {
+            None => self.report(
+                Severity::Error,
+                line,
+                1,
+                format!("start_pos [{}, {}] is outside of map 0", i, j),
+            ),
+            Some(Some(MapBlockTypes::NotWalkable)) => self.report(
+                Severity::Error,
+                line,
+                1,
+                format!("start_pos [{}, {}] is inside a wall", i, j),
+            ),
+            Some(_) => {}
+        }
+    }
+
+    /// edges to other maps, reported at the map they belong to
+    fn check_edges(&mut self, maps: &[Map]) -> Vec<Edges> {
+        let (edges, errors) = crate::edges::collect_edges(&self.settings.maps, maps);
+        let sources = map_source_lines(self.contents);
+        let line = |map: usize| sources.get(map).map_or(1, |source| source.start);
+        for error in errors {
+            let map = match error {
+                EdgeError::UnknownMap { map, .. }
+                | EdgeError::OutOfBounds { map, .. }
+                | EdgeError::IntoWall { map, .. } => map,
+            };
+            self.report(Severity::Error, line(map), 1, error.to_string());
+        }
+        edges
+    }
+
+    /// the levels of the dungeon, reported at its section, and stairs down which lead nowhere
+    fn check_dungeon(&mut self, maps: &[Map]) -> Vec<usize> {
+        let dungeon = self.settings.dungeon.as_ref();
+        let (levels, errors) = crate::depth::collect_levels(dungeon, &self.settings.maps, maps);
+        let line = self.key_line("dungeon");
+        for error in errors {
+            self.report(Severity::Error, line, 1, error.to_string());
+        }
+        let generator = dungeon.and_then(|dungeon| dungeon.generate.as_ref());
+        if let Some(generator) = generator {
+            if let Err(e) = generator.generate_level(maps.len(), 1, true) {
+                self.report(
+                    Severity::Error,
+                    line,
+                    1,
+                    format!("generate: {}", e.problem()),
+                );
+            }
+        }
+        let max_depth = dungeon.and_then(|dungeon| dungeon.max_depth);
+
+        let sources = map_source_lines(self.contents);
+        for (m, map) in maps.iter().enumerate() {
+            if !map
+                .iter()
+                .flatten()
+                .any(|block| *block == MapBlockTypes::StairsDown)
+            {
+                continue;
+            }
+            let depth = levels
+                .iter()
+                .position(|level| *level == m)
+                .map_or(1, |d| d + 2);
+            let generated = generator.is_some() && max_depth.map_or(true, |max| depth <= max);
+            if depth > levels.len() && !generated {
+                let line = sources.get(m).map_or(1, |source| source.start);
+                self.report(
+                    Severity::Warning,
+                    line,
+                    1,
+                    format!(
+                        "the stairs down on map {} lead nowhere, the dungeon has no level {}",
+                        m, depth
+                    ),
+                );
+            }
+        }
+        levels
+    }
+
+    /// things the player can never get to, only checked with a usable start_pos
+    fn check_reachability(&mut self, maps: &[Map], edges: &[Edges], levels: &[usize]) {
+        let [i, j] = self.settings.start_pos;
+        match maps
+            .first()
+            .and_then(|map| map.get(j))
+            .and_then(|row| row.get(i))
+        {
+            None | Some(MapBlockTypes::NotWalkable) => return,
+            Some(_) => {}
+        }
+        let reachability = Reachability::from(maps, edges, levels, 0, Pos { i, j });
+
+        let sources = map_source_lines(self.contents);
+        for m in 0..maps.len() {
+            if !reachability.map_reached(m) {
+                let line = sources.get(m).map_or(1, |source| source.start);
+                self.report(
+                    Severity::Warning,
+                    line,
+                    1,
+                    format!("map {} can not be reached from start_pos", m),
+                );
+            }
+        }
+
+        for (m, map) in self.map_tiles().iter().enumerate() {
+            for (tile_j, row) in map.iter().enumerate() {
+                for (tile_i, tile) in row.iter().enumerate() {
+                    let message = match maps[m][tile_j][tile_i] {
+                        MapBlockTypes::ItemTrigger(id)
+                            if reachability.map_reached(m)
+                                && !reachability.reached(m, tile_j, tile_i) =>
+                        {
+                            format!("item {} can not be reached from start_pos", id)
+                        }
+                        MapBlockTypes::EnemyTrigger(id)
+                            if reachability.map_reached(m)
+                                && !reachability.reached(m, tile_j, tile_i) =>
+                        {
+                            format!("enemy {} can not be reached from start_pos", id)
+                        }
+                        MapBlockTypes::TeleportTrigger(target, j, i)
+                            if is_dead_end(
+                                maps,
+                                edges,
+                                levels,
+                                [m, tile_j, tile_i],
+                                [target, j, i],
+                            ) =>
+                        {
+                            format!(
+                                "teleport target {} {} on map {} is a dead end, there is no way on from there",
+                                j, i, target
+                            )
+                        }
+                        _ => continue,
+                    };
+                    self.report_tile(Severity::Warning, tile, message);
+                }
+            }
+        }
+    }
+
+    /// ids used in the definitions, reported at the section they are in
+    /// problems with the things an action refers to
+    fn check_action(
+        &self,
+        action: &Action,
+        maps: &[Vec<Vec<Option<MapBlockTypes>>>],
+    ) -> Vec<(Severity, String)> {
+        match action {
+            Action::GiveItem(item) | Action::TakeItem(item) => self.check_item(item),
+            Action::StartFight(enemy) => self.check_enemy(enemy),
+            Action::Teleport(map, j, i) => check_target(maps, *map, *j, *i),
+            _ => vec![],
+        }
+    }
+
+    fn check_references(&mut self, maps: &[Vec<Vec<Option<MapBlockTypes>>>], names: &Names) {
+        let s = self.settings;
+        let mut found = vec![];
+        for chest in &s.chests {
+            for item in chest.items.iter().chain(&chest.key) {
+                found.push(("chests", self.check_item(item)));
+            }
+        }
+        for merchant in &s.merchants {
+            for entry in &merchant.stock {
+                found.push(("merchants", self.check_item(&entry.item)));
+            }
+        }
+        for door in &s.doors {
+            if let Some(key) = &door.key {
+                found.push(("doors", self.check_item(key)));
+            }
+        }
+        for trap in &s.traps {
+            if let Some(enemy) = &trap.enemy {
+                found.push(("traps", self.check_enemy(enemy)));
+            }
+            if let Some([map, j, i]) = trap.target {
+                found.push(("traps", check_target(maps, map, j, i)));
+            }
+        }
+        for quest in &s.quests {
+            for objective in &quest.objectives {
+                match objective {
+                    Objective::CollectItem(item) => found.push(("quests", self.check_item(item))),
+                    Objective::DefeatEnemy(enemy) => {
+                        found.push(("quests", self.check_enemy(enemy)))
+                    }
+                    _ => {}
+                }
+            }
+            for action in &quest.rewards {
+                found.push(("quests", self.check_action(action, maps)));
+            }
+        }
+        for npc in &s.npcs {
+            let mut problems = vec![];
+            let has_node = |id: &str| npc.nodes.iter().any(|node| node.id == id);
+            if !has_node(&npc.start) {
+                problems.push((
+                    Severity::Error,
+                    format!(
+                        "npc {} starts with node '{}', which does not exist",
+                        npc.id, npc.start
+                    ),
+                ));
+            }
+            for node in &npc.nodes {
+                for choice in &node.choices {
+                    match &choice.next {
+                        Some(next) if !has_node(next) => problems.push((
+                            Severity::Error,
+                            format!(
+                                "a choice of node '{}' of npc {} leads to node '{}', which does not exist",
+                                node.id, npc.id, next
+                            ),
+                        )),
+                        _ => {}
+                    }
+                    for action in &choice.outcomes {
+                        problems.extend(self.check_action(action, maps));
+                    }
+                }
+            }
+            found.push(("npcs", problems));
+        }
+        for tile in &s.conditional_tiles {
+            for raw in [&tile.then, &tile.otherwise] {
+                let problems = match raw.resolve(names) {
+                    Ok(block) => self.check_block(&block, maps),
+                    Err(e) => vec![(Severity::Error, e.to_string())],
+                };
+                found.push(("conditional_tiles", problems));
+            }
+        }
+        for event in &s.events {
+            for action in &event.actions {
+                found.push(("events", self.check_action(action, maps)));
+            }
+        }
+
+        for (section, problems) in found {
+            let line = self.key_line(section);
+            for (severity, message) in problems {
+                self.report(severity, line, 1, message);
+            }
+        }
+    }
+}
+
+/// the parsed maps, tiles which could not be parsed are already reported and count as walls
+fn known_blocks(maps: &[Vec<Vec<Option<MapBlockTypes>>>]) -> Vec<Map> {
+    maps.iter()
+        .map(|map| {
+            map.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|block| block.clone().unwrap_or(MapBlockTypes::NotWalkable))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// whether a player who used the teleport at `map` `j` `i` can neither walk back
+/// to it nor get to another map from where it leads
+fn is_dead_end(
+    maps: &[Map],
+    edges: &[Edges],
+    levels: &[usize],
+    [map, j, i]: [usize; 3],
+    [target, target_j, target_i]: [usize; 3],
+) -> bool {
+    let exists = maps
+        .get(target)
+        .and_then(|map| map.get(target_j))
+        .and_then(|row| row.get(target_i))
+        .is_some();
+    if !exists {
+        return false;
+    }
+    let reachability = Reachability::from(
+        maps,
+        edges,
+        levels,
+        target,
+        Pos {
+            i: target_i,
+            j: target_j,
+        },
+    );
+    !reachability.reached(map, j, i)
+        && (0..maps.len()).all(|m| m == target || !reachability.map_reached(m))
+}
+
+fn check_target(
+    maps: &[Vec<Vec<Option<MapBlockTypes>>>],
+    map: usize,
+    j: usize,
+    i: usize,
+) -> Vec<(Severity, String)> {
+    let target = match maps.get(map) {
+        Some(target) => target,
+        None => return vec![(Severity::Error, format!("map {} does not exist", map))],
+    };
+    match target.get(j).and_then(|row| row.get(i)) {
+        None => vec![(
+            Severity::Error,
+            format!("teleport target {} {} is outside of map {}", j, i, map),
+        )],
+        Some(Some(MapBlockTypes::NotWalkable)) => vec![(
+            Severity::Warning,
+            format!("teleport target {} {} on map {} is a wall", j, i, map),
+        )],
+        Some(_) => vec![],
+    }
+}
+
+fn pipe_tiles(map_str: &str, source: &MapSource, lines: &[&str]) -> Vec<Vec<Tile>> {
+    let map_str = map_str
+        .replace('\r', "\n")
+        .replace("\\n", "\n")
+        .replace(' ', "\n");
+    let rows: Vec<&str> = map_str.lines().filter(|row| !row.is_empty()).collect();
+    // without one source line per row only the start of the map is known
+    let exact = source.rows.len() == rows.len();
+
+    let mut map = vec![];
+    for (j, row) in rows.iter().enumerate() {
+        let mut tiles = vec![];
+        for (text, offset) in split_row(row) {
+            let (line, column) = if exact {
+                let line = source.rows[j];
+                let source_row = lines[line - 1];
+                let column = split_row(source_row)
+                    .get(tiles.len())
+                    .map_or(1, |(_, offset)| offset + 1);
+                (line, column)
+            } else {
+                (source.start, offset + 1)
+            };
+            tiles.push(Tile {
+                text: text.to_string(),
+                line,
+                column,
+                origin: None,
+            });
+        }
+        map.push(tiles);
+    }
+    map
+}
+
+fn grid_tiles(grid: &str, source: &MapSource, lines: &[&str]) -> Vec<Vec<Tile>> {
+    let rows = crate::map_gen::grid_rows(grid);
+    let exact = source.rows.len() == rows.len();
+
+    let mut map = vec![];
+    for (j, row) in rows.iter().enumerate() {
+        let (line, offset) = if exact {
+            let line = source.rows[j];
+            let source_row = lines[line - 1];
+            let offset = source_row
+                .find(row)
+                .map_or(0, |index| source_row[..index].chars().count());
+            (line, offset)
+        } else {
+            (source.start, 0)
+        };
+        let tiles = row
+            .chars()
+            .enumerate()
+            .map(|(i, c)| Tile {
+                text: c.to_string(),
+                line,
+                column: offset + i + 1,
+                origin: None,
+            })
+            .collect();
+        map.push(tiles);
+    }
+    map
+}
+
+fn tiled_tiles(tiled: &TiledMap, source: &MapSource) -> Vec<Vec<Tile>> {
+    let mut map = vec![];
+    for (j, row) in tiled.cells.iter().enumerate() {
+        let tiles = (0..row.len())
+            .map(|i| Tile {
+                text: String::new(),
+                line: source.start,
+                column: 1,
+                origin: Some(format!("{} row {}, tile {}", tiled.file, j, i)),
+            })
+            .collect();
+        map.push(tiles);
+    }
+    map
+}
+
+fn generated_tiles(m: usize, generator: &Generator, source: &MapSource) -> Vec<Vec<Tile>> {
+    let raw = generator.generate(m).unwrap_or_default();
+    let mut map = vec![];
+    for (j, row) in raw.iter().enumerate() {
+        let tiles = row
+            .iter()
+            .enumerate()
+            .map(|(i, tile)| Tile {
+                text: tile.text(),
+                line: source.start,
+                column: 1,
+                origin: Some(format!("generated row {}, tile {}", j, i)),
+            })
+            .collect();
+        map.push(tiles);
+    }
+    map
+}
+
+/// reads the maps made in tiled and tries the generators,
+/// a map which can not be read or generated is reported and left empty
+fn load_maps(
+    contents: &str,
+    settings: &mut GameSettings,
+    dir: &std::path::Path,
+) -> Vec<Diagnostic> {
+    let sources = map_source_lines(contents);
+    let mut diagnostics = vec![];
+    for (m, definition) in settings.maps.iter_mut().enumerate() {
+        let error = match &mut definition.layout {
+            Layout::Tiled(tiled) => tiled.load(dir).err().map(|e| e.to_string()),
+            Layout::Generated(generator) => generator.generate(m).err().map(|e| e.problem()),
+            _ => None,
+        };
+        if let Some(message) = error {
+            diagnostics.push(Diagnostic {
+                severity: Severity::Error,
+                line: sources.get(m).map_or(1, |source| source.start),
+                column: 1,
+                message,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// tiles of a row with their offset in characters
+fn split_row(row: &str) -> Vec<(&str, usize)> {
+    let mut tiles = vec![];
+    let mut offset = 0;
+    for part in row.split('|') {
+        let trimmed = part.trim();
+        if !trimmed.is_empty() {
+            let leading = part.len() - part.trim_start().len();
+            tiles.push((trimmed, row[..offset + leading].chars().count()));
+        }
+        offset += part.len() + 1;
+    }
+    tiles
+}
+
+/// where a map of the `maps` list starts and which lines hold its rows
+#[derive(Debug, Clone, Default)]
+struct MapSource {
+    start: usize,
+    rows: Vec<usize>,
+}
+
+fn map_source_lines(contents: &str) -> Vec<MapSource> {
+    let mut sources: Vec<MapSource> = vec![];
+    let mut in_maps = false;
+    // indentation of the `grid:` or `tiles:` key while the rows of its block are read
+    let mut grid_indent = None;
+    for (index, line) in contents.lines().enumerate() {
+        let line_nr = index + 1;
+        if !line.starts_with(' ') && !line.starts_with('-') && !line.trim().is_empty() {
+            in_maps = line.starts_with("maps:");
+            grid_indent = None;
+            continue;
+        }
+        if !in_maps || line.trim().is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        if let Some(grid_indent) = grid_indent {
+            if indent > grid_indent {
+                if let Some(source) = sources.last_mut() {
+                    source.rows.push(line_nr);
+                }
+                continue;
+            }
+        }
+        grid_indent = None;
+
+        let trimmed = line.trim_start();
+        let rest = match trimmed.strip_prefix("- ") {
+            Some(rest) => {
+                sources.push(MapSource {
+                    start: line_nr,
+                    rows: vec![],
+                });
+                rest.trim_start()
+            }
+            None => trimmed,
+        };
+        let layout = rest
+            .strip_prefix("grid:")
+            .or_else(|| rest.strip_prefix("tiles:"));
+        if let Some(value) = layout.map(str::trim_start) {
+            // only block scalars have one line per row
+            if value.starts_with('|') || value.starts_with('>') {
+                grid_indent = Some(line.len() - rest.len());
+            } else if value.contains('|') {
+                if let Some(source) = sources.last_mut() {
+                    source.rows.push(line_nr);
+                }
+            }
+        } else if rest.contains('|') {
+            if let Some(source) = sources.last_mut() {
+                source.rows.push(line_nr);
+            }
+        }
+    }
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::{validate_file, validate_scenario, Severity};
+    use std::path::Path;
+
+    fn scenario(maps: &str, start_pos: &str) -> String {
+        format!(
+            "maps:
+{}
+start_pos: {}
+global_items:
+    - id: 0
+      name: Sword
+      at: 2
+enemies:
+    - id: 0
+      name: Troll
+      hp: 10
+      type_: Enemy
+player:
+    name: Hans
+    character_type: Warrior
+    total_health: 30
+    rp_properties:
+        at: 1
+        aw: 1
+        rs: 1
+",
+            maps, start_pos
+        )
+    }
+
+    #[test]
+    fn test_valid_scenario() {
+        assert_eq!(validate_file("test.yaml"), vec![]);
+    }
+
+    #[test]
+    fn test_report_all_problems() {
+        let contents = scenario(
+            "    - >-
+        |x|_|I0|C0|
+        |x|I4|T(1,2)|
+        |_|E9|T(0,5,0)|T(3,0,0)|",
+            "[0, 0]",
+        ) + "chests:
+    - id: 0
+      items: [0, 7]
+traps:
+    - id: 0
+      kind: pit
+      target: [0, 0, 9]
+events:
+    - trigger: !on_turn 3
+      actions:
+          - !teleport [0, 0, 1]
+          - !teleport [0, 5, 0]
+          - !teleport [4, 0, 0]
+";
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                "4:10: error: row 1 of map 0 has 3 tiles, but the first row has 4",
+                "4:12: error: item 4 is not in global_items",
+                "4:15: error: teleport 'T(1,2)' needs a map, j and i like T(1,2,3) or an anchor like T(crypt.entrance)",
+                "5:12: error: enemy 9 is not in enemies",
+                "5:15: error: teleport target 5 0 is outside of map 0",
+                "5:24: error: map 3 does not exist",
+                "6:1: error: start_pos [0, 0] is inside a wall",
+                "24:1: error: item 7 is not in global_items",
+                "27:1: error: teleport target 0 9 is outside of map 0",
+                "31:1: error: teleport target 5 0 is outside of map 0",
+                "31:1: error: map 4 does not exist",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_reference_problems() {
+        let contents = scenario("    - '|_|N0|?0|'", "[0, 0]")
+            + "quests:
+    - id: 0
+      name: Hunt
+      objectives: []
+      rewards:
+          - !give_item 9
+npcs:
+    - id: 0
+      name: Hermit
+      start: hi
+      nodes:
+          - id: hello
+            text: Hello
+            choices:
+                - text: Fight
+                  next: bye
+                  outcomes:
+                      - !start_fight 5
+conditional_tiles:
+    - id: 0
+      condition: {flag: open}
+      then: I9
+      else: T(crypt.door)
+";
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                "21:1: error: item 9 is not in global_items",
+                "27:1: error: npc 0 starts with node 'hi', which does not exist",
+                "27:1: error: a choice of node 'hello' of npc 0 leads to node 'bye', which does not exist",
+                "27:1: error: enemy 5 is not in enemies",
+                "39:1: error: item 9 is not in global_items",
+                "39:1: error: there is no map called 'crypt'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_unreachable() {
+        let contents = scenario(
+            "    - >-
+        |_|_|x|I0|
+        |T(1,0,0)|x|x|x|
+    - >-
+        |_|x|_|
+    - >-
+        |_|",
+            "[0, 0]",
+        );
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                "3:16: warning: item 0 can not be reached from start_pos",
+                "4:10: warning: teleport target 0 0 on map 1 is a dead end, there is no way on from there",
+                "7:1: warning: map 2 can not be reached from start_pos",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_grid_problems() {
+        let contents = scenario(
+            "    - grid: |
+          #.D
+          #?.
+      legend:
+          D: !door 4",
+            "[1, 0]",
+        );
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                "3:13: error: door 4 is not defined",
+                "4:12: error: '?' is not in the legend",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_name_problems() {
+        let contents = scenario(
+            "    - name: hall
+      tiles: |
+          |_|T(crypt.entrance)|A(door)|
+          |_|T(crypt.exit)|M(cellar)|
+    - name: hall
+      grid: |
+          .h
+      legend:
+          h: !teleport hall.door",
+            "[0, 0]",
+        );
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                "4:14: error: there is no map called 'crypt'",
+                "5:14: error: there is no map called 'crypt'",
+                "5:28: error: there is no map called 'cellar'",
+                "6:1: error: there is already a map called 'hall'",
+                "6:1: warning: map 1 can not be reached from start_pos",
+                "8:12: warning: teleport target 0 2 on map 0 is a dead end, there is no way on from there",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_edge_problems() {
+        let contents = scenario(
+            "    - tiles: '|_|_| |_|_|'
+      edges:
+          east: {map: 1, offset: 1}
+          west: {map: crypt}
+    - '|_|_| |x|_|'",
+            "[0, 0]",
+        );
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                "2:1: error: the west side of map 0 leads to map 'crypt', which does not exist",
+                "2:1: error: walking off the east side of map 0 at row 0, tile 1 leads into a wall on map 1",
+                "2:1: error: walking off the east side of map 0 at row 1, tile 1 leads outside of map 1",
+                "6:1: warning: map 1 can not be reached from start_pos",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_tiled_problems() {
+        let dir = std::env::temp_dir().join(format!("tiled-validate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("hall.json"),
+            r#"{"width": 3, "height": 1, "tilewidth": 16, "tileheight": 16,
+                "tilesets": [{"firstgid": 1}],
+                "layers": [
+                    {"type": "tilelayer", "data": [2, 2, 4]},
+                    {"type": "objectgroup", "objects": [
+                        {"id": 1, "name": "5", "type": "enemy", "x": 16, "y": 0}
+                    ]}
+                ]}"#,
+        )
+        .unwrap();
+        let contents = scenario(
+            "    - tiled: hall.json
+      tileset: {1: wall, 2: path}
+    - tiled: cellar.tmx",
+            "[0, 0]",
+        );
+        let diagnostics = validate_scenario(&contents, &dir);
+        let mut found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(found[2].starts_with("4:1: error: can not read cellar.tmx"));
+        found.remove(2);
+        assert_eq!(
+            found,
+            vec![
+                "2:1: error: hall.json row 0, tile 2: tile 4 is not in the tileset of the map",
+                "2:1: error: hall.json row 0, tile 1: enemy 5 is not in enemies",
+                "4:1: warning: map 1 can not be reached from start_pos",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_generator_problems() {
+        let contents = scenario(
+            "    - tiles: '|_|T(deep.entrance)|'
+    - name: deep
+      generate: {algorithm: rooms, width: 12, height: 8, item_count: 1, items: [{id: 9}], exits: [[0, 0, 0]]}
+    - generate: {algorithm: caves, width: 3, height: 8}",
+            "[0, 0]",
+        );
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(found.len(), 3, "{:?}", found);
+        assert!(found[0].starts_with("3:1: error: generated row "));
+        assert!(found[0].ends_with(": item 9 is not in global_items"));
+        assert_eq!(
+            found[1..],
+            [
+                "5:1: error: a generated map needs to be at least 5x5",
+                "5:1: warning: map 2 can not be reached from start_pos",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_dungeon_problems() {
+        let contents = scenario(
+            "    - '|_|>|'
+    - name: crypt
+      tiles: '|<|E0|>|'
+    - '|<|I0|>|'",
+            "[0, 0]",
+        ) + "dungeon:\n    levels: [crypt, 2, cellar]\n";
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        // the stairs lead to map 2, so it can be reached
+        assert_eq!(
+            found,
+            [
+                "5:1: warning: the stairs down on map 2 lead nowhere, the dungeon has no level 3",
+                "24:1: error: level 3 of the dungeon is map 'cellar', which does not exist",
+            ]
+        );
+
+        let contents = scenario("    - '|_|>|'", "[0, 0]")
+            + "dungeon:
+    generate: {algorithm: caves, width: 20, height: 10, enemy_count: 1, enemies: [{id: 0, min_depth: 2}]}
+";
+        let diagnostics = validate_scenario(&contents, Path::new(""));
+        let found: Vec<String> = diagnostics.iter().map(|d| d.to_string()).collect();
+
+        assert_eq!(
+            found,
+            [
+                "21:1: error: generate: enemies: the pool needs at least one entry with a weight above 0 for depth 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_yaml_errors() {
+        let diagnostics = validate_scenario("maps: [\n  - |x|\n", Path::new(""));
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(diagnostics[0].line > 1);
+    }
+}